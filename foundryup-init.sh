@@ -216,11 +216,11 @@ post_install() {
             say "Run 'foundryup' to install Foundry."
             ;;
         *)
-            say "To get started, add foundryup to your PATH:"
+            say "To get started, run:"
             say ""
-            say "  export PATH=\"\$PATH:$FOUNDRYUP_BIN_DIR\""
+            say "  $FOUNDRYUP_BIN_DIR/foundryup"
             say ""
-            say "Then run 'foundryup' to install Foundry."
+            say "It installs Foundry and adds it to your PATH (pass --no-modify-path to skip)."
             ;;
     esac
 }
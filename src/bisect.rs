@@ -0,0 +1,187 @@
+use crate::{
+    cli::BisectArgs,
+    config::Config,
+    download::Downloader,
+    install,
+    platform::Target,
+    releases::{self, NightlyRequest, Release},
+    say, warn,
+};
+use eyre::{Result, WrapErr, bail};
+
+pub(crate) async fn run(config: &Config, args: &BisectArgs, force: bool) -> Result<()> {
+    let repo = config.network.repo;
+    for endpoint in [&args.good, &args.bad] {
+        if releases::is_channel(endpoint) {
+            bail!("{endpoint} is a moving channel, pass a concrete version instead");
+        }
+    }
+
+    say!("fetching releases of {repo}...");
+    let downloader = Downloader::new()?;
+    let all = releases::fetch_releases(&downloader, repo, |releases| {
+        [&args.good, &args.bad]
+            .iter()
+            .all(|endpoint| releases.iter().any(|release| matches_endpoint(release, endpoint)))
+    })
+    .await?;
+
+    let mut candidates = bisect_range(&all, &args.good, &args.bad)?;
+    let steps = (candidates.len() - 1).next_power_of_two().ilog2();
+    say!(
+        "bisecting {} nightlies between {} ({}) and {} ({}), roughly {steps} steps",
+        candidates.len() - 2,
+        candidates[0].tag,
+        candidates[0].date(),
+        candidates[candidates.len() - 1].tag,
+        candidates[candidates.len() - 1].date(),
+    );
+
+    let target = Target::detect(None, None)?;
+
+    // Invariant: `candidates[good]` is good and `candidates[bad]` is bad.
+    let (mut good, mut bad) = (0, candidates.len() - 1);
+    let mut skipped = Vec::new();
+    while bad - good > 1 {
+        let mid = good + (bad - good) / 2;
+        let candidate = candidates[mid];
+        say!("testing {} ({}), {} nightlies left", candidate.tag, candidate.date(), bad - good - 1);
+
+        let tag =
+            match install::install_release(config, &downloader, &candidate.tag, &target, force)
+                .await
+            {
+                Ok(tag) => tag,
+                Err(e) => {
+                    warn!("skipping {}, it could not be installed: {e}", candidate.tag);
+                    skipped.push(candidates.remove(mid));
+                    bad -= 1;
+                    continue;
+                }
+            };
+        if run_command(config, &tag, &args.command).await? {
+            say!("{} is good", candidate.tag);
+            good = mid;
+        } else {
+            say!("{} is bad", candidate.tag);
+            bad = mid;
+        }
+    }
+
+    let first_bad = &candidates[bad];
+    say!("first bad nightly: {} (published {})", first_bad.tag, first_bad.date());
+    if let Some(commit) = first_bad.tag.strip_prefix("nightly-") {
+        say!("commit: https://github.com/{repo}/commit/{commit}");
+    }
+    say!("last good version: {} (published {})", candidates[good].tag, candidates[good].date());
+
+    // Skipped nightlies published between the last good and the first bad one may be the culprit.
+    let undecided: Vec<_> = skipped
+        .iter()
+        .filter(|release| {
+            release.published_at > candidates[good].published_at
+                && release.published_at < first_bad.published_at
+        })
+        .map(|release| release.tag.as_str())
+        .collect();
+    if !undecided.is_empty() {
+        warn!(
+            "{} could not be installed, the first bad nightly may be one of them",
+            undecided.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the releases from `good` to `bad` in chronological order: the two endpoints and every
+/// nightly published between them.
+fn bisect_range<'a>(releases: &'a [Release], good: &str, bad: &str) -> Result<Vec<&'a Release>> {
+    let find = |endpoint: &str| {
+        releases
+            .iter()
+            .position(|release| matches_endpoint(release, endpoint))
+            .ok_or_else(|| eyre::eyre!("no release found for {endpoint}"))
+    };
+    let (good_idx, bad_idx) = (find(good)?, find(bad)?);
+
+    // Releases are sorted from newest to oldest.
+    if bad_idx >= good_idx {
+        bail!("the good version {good} must be older than the bad version {bad}");
+    }
+
+    let mut range = vec![&releases[bad_idx]];
+    range.extend(releases[bad_idx + 1..good_idx].iter().filter(|release| release.is_nightly()));
+    range.push(&releases[good_idx]);
+    range.reverse();
+    Ok(range)
+}
+
+fn matches_endpoint(release: &Release, endpoint: &str) -> bool {
+    match NightlyRequest::parse(endpoint) {
+        Some(request) => request.matches(release),
+        None => release.tag == install::normalize_version(endpoint).1,
+    }
+}
+
+/// Runs the command with the binaries of the given version first in `PATH`, returning whether it
+/// succeeded.
+async fn run_command(config: &Config, version: &str, command: &[String]) -> Result<bool> {
+    let version_dir = config.version_dir(config.network.repo, version);
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let path =
+        std::env::join_paths(std::iter::once(version_dir).chain(std::env::split_paths(&path)))?;
+
+    let status = tokio::process::Command::new(&command[0])
+        .args(&command[1..])
+        .env("PATH", path)
+        .status()
+        .await
+        .wrap_err_with(|| format!("failed to run {}", command[0]))?;
+
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, date: &str) -> Release {
+        Release {
+            tag: tag.to_string(),
+            published_at: format!("{date}T00:00:00Z"),
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn range_between_endpoints() {
+        let releases = [
+            release("nightly-eeee0000", "2026-10-05"),
+            release("nightly-dddd0000", "2026-10-04"),
+            release("v1.5.0", "2026-10-03"),
+            release("nightly-cccc0000", "2026-10-03"),
+            release("nightly-bbbb0000", "2026-10-02"),
+            release("nightly-aaaa0000", "2026-10-01"),
+        ];
+
+        let range = bisect_range(&releases, "nightly-2026-10-01", "nightly@dddd").unwrap();
+        let tags: Vec<_> = range.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(
+            tags,
+            ["nightly-aaaa0000", "nightly-bbbb0000", "nightly-cccc0000", "nightly-dddd0000"]
+        );
+
+        let range = bisect_range(&releases, "1.5.0", "nightly-eeee0000").unwrap();
+        let tags: Vec<_> = range.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, ["v1.5.0", "nightly-dddd0000", "nightly-eeee0000"]);
+
+        // A stable release published the same day as a nightly is not a nightly endpoint.
+        let range = bisect_range(&releases, "nightly-2026-10-03", "nightly-eeee0000").unwrap();
+        let tags: Vec<_> = range.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, ["nightly-cccc0000", "nightly-dddd0000", "nightly-eeee0000"]);
+
+        assert!(bisect_range(&releases, "nightly-eeee0000", "nightly-aaaa0000").is_err());
+        assert!(bisect_range(&releases, "nightly@ffff", "nightly-eeee0000").is_err());
+    }
+}
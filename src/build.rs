@@ -0,0 +1,155 @@
+use crate::config::Config;
+use eyre::{Result, WrapErr, bail};
+use fs_err as fs;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde_json::Value;
+use std::{
+    collections::VecDeque,
+    io::Write,
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+};
+use tracing::debug;
+
+/// Maximum number of compiler errors printed when a build fails.
+const MAX_ERRORS: usize = 5;
+
+/// Number of trailing cargo output lines printed when a build fails without compiler errors.
+const TAIL_LINES: usize = 20;
+
+/// A message printed by `cargo build --message-format=json`.
+#[derive(Debug, PartialEq, Eq)]
+enum Message {
+    /// A crate finished compiling.
+    Artifact(String),
+    /// A compiler diagnostic, rendered as rustc prints it.
+    Diagnostic { level: String, rendered: String },
+}
+
+/// Runs a `cargo build` command, writing its output to a log file in `foundry_dir/logs` while
+/// showing a spinner.
+///
+/// On failure, the compiler errors (or the last lines of output) and the log path are printed. The
+/// log of a successful build is removed.
+pub(crate) async fn cargo_build(config: &Config, mut cmd: Command, name: &str) -> Result<()> {
+    let logs_dir = config.foundry_dir.join("logs");
+    fs::create_dir_all(&logs_dir)?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let log_path = logs_dir.join(format!("{name}-{timestamp}.log"));
+    let log = Arc::new(Mutex::new(fs::File::create(&log_path)?));
+
+    cmd.arg("--message-format=json").stdout(Stdio::piped()).stderr(Stdio::piped());
+    debug!("running {cmd:?}");
+    let mut child = cmd.spawn().wrap_err("failed to run cargo build")?;
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner.set_message(format!("building {name}..."));
+
+    // Cargo reports its progress and failures outside of the compiler on stderr.
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let stderr_task = tokio::spawn({
+        let log = log.clone();
+        async move {
+            let mut tail = VecDeque::with_capacity(TAIL_LINES);
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = writeln!(log.lock().unwrap(), "{line}");
+                if tail.len() == TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }
+            tail
+        }
+    });
+
+    let mut errors = Vec::new();
+    let mut compiled = 0;
+    let mut lines = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+    while let Some(line) = lines.next_line().await? {
+        match parse_message(&line) {
+            Some(Message::Artifact(krate)) => {
+                compiled += 1;
+                spinner
+                    .set_message(format!("building {name}: compiled {krate} ({compiled} crates)"));
+            }
+            Some(Message::Diagnostic { level, rendered }) => {
+                let _ = write!(log.lock().unwrap(), "{rendered}");
+                if level == "error" && !rendered.starts_with("error: aborting due to") {
+                    errors.push(rendered);
+                }
+            }
+            None => {}
+        }
+    }
+
+    let status = child.wait().await.wrap_err("failed to run cargo build")?;
+    let tail = stderr_task.await?;
+    spinner.finish_and_clear();
+
+    // Only the logs of failed builds are kept, until `foundryup gc`.
+    if status.success() {
+        drop(log);
+        fs::remove_file(&log_path)?;
+        debug!("built {name}");
+        return Ok(());
+    }
+
+    if errors.is_empty() {
+        for line in tail {
+            eprintln!("{line}");
+        }
+    } else {
+        for error in errors.iter().take(MAX_ERRORS) {
+            eprint!("{error}");
+        }
+        if errors.len() > MAX_ERRORS {
+            eprintln!("... and {} more errors", errors.len() - MAX_ERRORS);
+        }
+    }
+    bail!("cargo build failed, see the full log at {}", log_path.display())
+}
+
+fn parse_message(line: &str) -> Option<Message> {
+    let json: Value = serde_json::from_str(line).ok()?;
+    match json["reason"].as_str()? {
+        "compiler-artifact" => {
+            Some(Message::Artifact(json["target"]["name"].as_str()?.to_string()))
+        }
+        "compiler-message" => Some(Message::Diagnostic {
+            level: json["message"]["level"].as_str()?.to_string(),
+            rendered: json["message"]["rendered"].as_str()?.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cargo_messages() {
+        let artifact = r#"{"reason":"compiler-artifact","target":{"name":"forge"},"fresh":false}"#;
+        assert_eq!(parse_message(artifact), Some(Message::Artifact("forge".to_string())));
+
+        let error = r#"{"reason":"compiler-message","message":{"level":"error","rendered":"error[E0425]: cannot find value `x`\n"}}"#;
+        assert_eq!(
+            parse_message(error),
+            Some(Message::Diagnostic {
+                level: "error".to_string(),
+                rendered: "error[E0425]: cannot find value `x`\n".to_string(),
+            })
+        );
+
+        assert_eq!(parse_message(r#"{"reason":"build-finished","success":false}"#), None);
+        assert_eq!(parse_message("   Compiling forge v0.0.0"), None);
+    }
+}
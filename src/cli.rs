@@ -1,4 +1,12 @@
-use clap::{CommandFactory, Parser};
+use crate::{
+    config::Config,
+    git, install,
+    metadata::{ChannelAliases, TagCache},
+    process::InUsePolicy,
+    releases,
+};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
 
 /// The installer for Foundry.
 ///
@@ -8,6 +16,9 @@ use clap::{CommandFactory, Parser};
 #[derive(Debug, Parser)]
 #[command(name = "foundryup", version = crate::config::LONG_VERSION, about)]
 pub(crate) struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
     /// Update foundryup to the latest version
     #[arg(short = 'U', long = "update")]
     pub update: bool,
@@ -17,11 +28,19 @@ pub(crate) struct Cli {
     pub repo: Option<String>,
 
     /// Build and install a specific branch
-    #[arg(short = 'b', long, conflicts_with = "pr")]
+    #[arg(short = 'b', long, conflicts_with_all = ["pr", "tag", "git_ref"])]
     pub branch: Option<String>,
 
-    /// Install a specific version from built binaries (e.g., stable, nightly, 0.3.0)
-    #[arg(id = "ver", short = 'i', long = "install", value_name = "VERSION")]
+    /// Install a specific version from built binaries (e.g., stable, nightly, 1.5, latest)
+    ///
+    /// Nightlies can also be selected by date (nightly-2026-10-01) or commit (nightly@1c57854).
+    #[arg(
+        id = "ver",
+        short = 'i',
+        long = "install",
+        value_name = "VERSION",
+        add = ArgValueCandidates::new(remote_versions)
+    )]
     pub version: Option<String>,
 
     /// List installed versions
@@ -29,7 +48,12 @@ pub(crate) struct Cli {
     pub list: bool,
 
     /// Use a specific installed version
-    #[arg(short = 'u', long = "use", value_name = "VERSION")]
+    #[arg(
+        short = 'u',
+        long = "use",
+        value_name = "VERSION",
+        add = ArgValueCandidates::new(installed_versions)
+    )]
     pub use_version: Option<String>,
 
     /// Build and install a local repository
@@ -37,13 +61,45 @@ pub(crate) struct Cli {
     pub path: Option<std::path::PathBuf>,
 
     /// Build and install a specific Pull Request
-    #[arg(short = 'P', long, conflicts_with = "branch")]
+    #[arg(short = 'P', long, conflicts_with_all = ["branch", "tag", "git_ref"])]
     pub pr: Option<u64>,
 
+    /// Build and install a specific tag (e.g., v1.6.0-rc1)
+    #[arg(long, conflicts_with = "git_ref")]
+    pub tag: Option<String>,
+
+    /// Build and install an arbitrary git ref (e.g., refs/pull/123/merge)
+    #[arg(long = "ref", value_name = "REF")]
+    pub git_ref: Option<String>,
+
     /// Build and install a specific commit
     #[arg(short = 'C', long)]
     pub commit: Option<String>,
 
+    /// Only install and link these binaries (e.g., forge,cast)
+    ///
+    /// The selection is remembered for later installs.
+    #[arg(long, env = "FOUNDRYUP_COMPONENTS", value_name = "BINS", value_delimiter = ',')]
+    pub components: Vec<String>,
+
+    /// How much history to clone for source builds
+    ///
+    /// An existing clone keeps its history, except that a shallow clone is completed with `full`.
+    #[arg(long, value_enum, value_name = "MODE", env = "FOUNDRYUP_CLONE", default_value_t)]
+    pub clone: CloneMode,
+
+    /// URL to clone source builds from, `{repo}` is replaced with the repository
+    ///
+    /// E.g. `git@github.com:{repo}.git` to clone over SSH, or the URL of a mirror.
+    #[arg(long, value_name = "URL", env = "FOUNDRYUP_GIT_URL", default_value = git::DEFAULT_URL)]
+    pub git_url: String,
+
+    /// Repository to borrow git objects from when cloning for source builds
+    ///
+    /// Defaults to the clone of the upstream repository when building a fork.
+    #[arg(long, value_name = "DIR", env = "FOUNDRYUP_GIT_REFERENCE")]
+    pub git_reference: Option<std::path::PathBuf>,
+
     /// Number of CPUs to use for building (default: all)
     #[arg(short = 'j', long)]
     pub jobs: Option<u32>,
@@ -56,6 +112,16 @@ pub(crate) struct Cli {
     #[arg(long)]
     pub cargo_features: Option<String>,
 
+    /// Build without `-C target-cpu=native`, so the binaries also run on other CPUs
+    ///
+    /// Has no effect when RUSTFLAGS is set.
+    #[arg(
+        long,
+        env = "FOUNDRYUP_PORTABLE",
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    pub portable: bool,
+
     /// Install binaries for a specific network (e.g., tempo)
     #[arg(short = 'n', long)]
     pub network: Option<Network>,
@@ -64,6 +130,27 @@ pub(crate) struct Cli {
     #[arg(short = 'f', long)]
     pub force: bool,
 
+    /// Wait for running Foundry binaries to exit before installing
+    #[arg(long, conflicts_with = "kill")]
+    pub wait: bool,
+
+    /// Terminate running Foundry binaries before installing
+    #[arg(long)]
+    pub kill: bool,
+
+    /// Skip confirmation prompts
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
+
+    /// Do not add the bin directory to PATH in the shell profiles
+    #[arg(
+        long,
+        global = true,
+        env = "FOUNDRYUP_NO_MODIFY_PATH",
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    pub no_modify_path: bool,
+
     /// Install a specific architecture (amd64, arm64)
     #[arg(long)]
     pub arch: Option<String>,
@@ -72,16 +159,297 @@ pub(crate) struct Cli {
     #[arg(long)]
     pub platform: Option<String>,
 
+    /// Do not check for foundryup updates in the background
+    ///
+    /// The check is also skipped when stdout is not a terminal or `CI` is set.
+    #[arg(
+        long,
+        global = true,
+        env = "FOUNDRYUP_NO_UPDATE_CHECK",
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    pub no_update_check: bool,
+
+    /// Seconds between background checks for foundryup updates
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        env = "FOUNDRYUP_UPDATE_CHECK_INTERVAL",
+        default_value_t = 86400
+    )]
+    pub update_check_interval: u64,
+
+    /// Seconds to wait for another foundryup process to finish installing or activating, or for
+    /// running Foundry binaries to exit with --wait
+    #[arg(long, value_name = "SECONDS", env = "FOUNDRYUP_LOCK_TIMEOUT", default_value_t = 300)]
+    pub lock_timeout: u64,
+
     /// Generate shell completions
+    ///
+    /// To also complete installed and released versions, source `COMPLETE=<SHELL> foundryup`.
     #[arg(long, value_name = "SHELL")]
     pub completions: Option<clap_complete::Shell>,
 }
 
+impl Cli {
+    pub(crate) fn in_use_policy(&self) -> InUsePolicy {
+        if self.wait {
+            InUsePolicy::Wait
+        } else if self.kill {
+            InUsePolicy::Kill
+        } else {
+            InUsePolicy::Fail
+        }
+    }
+
+    pub(crate) fn lock_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.lock_timeout)
+    }
+
+    /// Returns whether to check for foundryup updates in the background.
+    pub(crate) fn check_updates(&self) -> bool {
+        use std::io::IsTerminal;
+        !self.no_update_check && std::io::stdout().is_terminal() && std::env::var_os("CI").is_none()
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Commands {
+    /// Verify installed binaries against their attested or recorded digests
+    Verify(VerifyArgs),
+
+    /// List recent nightly releases with their publication dates
+    Nightlies(NightliesArgs),
+
+    /// Find the first bad nightly between a good and a bad version
+    ///
+    /// Installs nightlies between the two versions and runs the command with each of them first in
+    /// PATH, binary searching for the first one the command fails with.
+    Bisect(BisectArgs),
+
+    /// Reactivate the previously active version
+    Rollback,
+
+    /// Remove the git worktrees and failed build logs of source builds
+    Gc,
+
+    /// Add or remove binaries of the active version
+    #[command(subcommand)]
+    Component(ComponentCommand),
+
+    /// Add the bin directory to PATH in the shell profiles
+    ///
+    /// Writes scripts setting up PATH to the foundry directory (`env`, `env.fish`, `env.nu` and
+    /// `env.ps1`) and sources them from the detected bash, zsh, fish, nushell and PowerShell
+    /// profiles. Runs on the first install unless --no-modify-path is passed.
+    SetupPath(SetupPathArgs),
+
+    /// Manage the foundryup installation itself
+    #[command(name = "self", subcommand)]
+    Self_(SelfCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum SelfCommand {
+    /// Update foundryup to the latest version, or switch to a specific one
+    Update(SelfUpdateArgs),
+
+    /// Restore the foundryup binary replaced by the last update
+    Rollback,
+
+    /// Remove foundryup, the installed versions and the PATH setup
+    ///
+    /// Data of the Foundry binaries, like keystores or the RPC cache, is kept.
+    Uninstall,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SelfUpdateArgs {
+    /// Install this version of foundryup, even if it is older than the installed one
+    #[arg(long, value_name = "VERSION", env = "FOUNDRYUP_VERSION")]
+    pub to: Option<String>,
+
+    /// The release channel to update from
+    #[arg(long, value_enum, env = "FOUNDRYUP_CHANNEL", default_value_t)]
+    pub channel: UpdateChannel,
+}
+
+impl SelfUpdateArgs {
+    /// Returns the arguments set through the environment, used by `--update` and the update check.
+    pub(crate) fn from_env() -> eyre::Result<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let channel = match var("FOUNDRYUP_CHANNEL") {
+            Some(channel) => UpdateChannel::from_str(&channel, true).map_err(|_| {
+                eyre::eyre!("invalid FOUNDRYUP_CHANNEL '{channel}', expected stable or prerelease")
+            })?,
+            None => UpdateChannel::default(),
+        };
+        Ok(Self { to: var("FOUNDRYUP_VERSION"), channel })
+    }
+}
+
+/// The releases foundryup updates itself from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum UpdateChannel {
+    /// The latest stable release
+    #[default]
+    Stable,
+    /// The latest release, including prereleases
+    Prerelease,
+}
+
+impl UpdateChannel {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Prerelease => "prerelease",
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SetupPathArgs {
+    /// Remove the lines added to the shell profiles and the env scripts
+    #[arg(long)]
+    pub undo: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum ComponentCommand {
+    /// Install and link additional binaries
+    Add {
+        #[arg(required = true, value_delimiter = ',')]
+        components: Vec<String>,
+    },
+
+    /// Unlink and remove binaries
+    Remove {
+        #[arg(required = true, value_delimiter = ',')]
+        components: Vec<String>,
+    },
+
+    /// List the binaries and whether they are selected and installed
+    List,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct VerifyArgs {
+    /// Verify every installed version instead of only the active one
+    #[arg(long)]
+    pub all: bool,
+
+    /// Download the attestations again instead of using the digests recorded at install time
+    #[arg(long)]
+    pub refetch: bool,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct NightliesArgs {
+    /// Only list nightlies published on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<String>,
+
+    /// Maximum number of nightlies to list
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct BisectArgs {
+    /// A version the command succeeds with (e.g., v1.4.0, nightly-2026-10-01, nightly@1c57854)
+    #[arg(long, value_name = "VERSION")]
+    pub good: String,
+
+    /// A newer version the command fails with
+    #[arg(long, value_name = "VERSION")]
+    pub bad: String,
+
+    /// The command to run; a zero exit status marks the installed nightly as good
+    #[arg(last = true, required = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+}
+
+/// How much of a repository is cloned for source builds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum CloneMode {
+    /// The complete history
+    Full,
+    /// The complete history, downloading file contents only when checked out
+    #[default]
+    Partial,
+    /// Only the built commit
+    Shallow,
+}
+
+impl CloneMode {
+    /// Returns the `git clone` arguments for the mode.
+    pub(crate) fn args(self) -> &'static [&'static str] {
+        match self {
+            Self::Full => &[],
+            Self::Partial => &["--filter=blob:none"],
+            Self::Shallow => &["--depth=1"],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub(crate) enum Network {
     Tempo,
 }
 
+/// Returns the configuration of the network selected on the command line being completed.
+///
+/// Completers are not given the other arguments, they are read from the completed command line.
+fn completion_config() -> Option<Config> {
+    let args: Vec<String> = std::env::args().collect();
+    let network = args.iter().enumerate().find_map(|(i, arg)| {
+        let value = match arg.as_str() {
+            "--network" | "-n" => args.get(i + 1)?.as_str(),
+            arg => arg.strip_prefix("--network=")?,
+        };
+        Network::from_str(value, true).ok()
+    });
+    Config::new(network).ok()
+}
+
+/// Completes `--use` with the installed versions of the selected network and the channels they
+/// were installed from.
+fn installed_versions() -> Vec<CompletionCandidate> {
+    let Some(config) = completion_config() else { return Vec::new() };
+    let repo = config.network.repo;
+
+    let mut candidates = Vec::new();
+    if let Ok(aliases) = ChannelAliases::read(&config.versions_dir.join(repo)) {
+        candidates.extend(aliases.iter().map(|(channel, version)| {
+            CompletionCandidate::new(channel).help(Some(version.to_string().into()))
+        }));
+    }
+    let installed = install::installed_versions(&config).unwrap_or_default();
+    candidates.extend(
+        installed
+            .into_iter()
+            .filter(|(installed_repo, _)| installed_repo == repo)
+            .map(|(_, version)| CompletionCandidate::new(version)),
+    );
+    candidates
+}
+
+/// Completes `--install` with the channels and the tags of the selected network cached by the
+/// last fetch.
+fn remote_versions() -> Vec<CompletionCandidate> {
+    let mut candidates: Vec<_> =
+        releases::CHANNELS.iter().chain(["latest"].iter()).map(CompletionCandidate::new).collect();
+
+    let Some(config) = completion_config() else { return candidates };
+    let tags = TagCache::read(&config.cache_dir(config.network.repo));
+    candidates.extend(tags.into_iter().map(CompletionCandidate::new));
+    candidates
+}
+
 pub(crate) fn print_completions(shell: clap_complete::Shell) {
     clap_complete::generate(shell, &mut Cli::command(), "foundryup", &mut std::io::stdout());
 }
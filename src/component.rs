@@ -0,0 +1,107 @@
+use crate::{
+    cli::ComponentCommand,
+    config::Config,
+    download::Downloader,
+    install,
+    metadata::VersionMetadata,
+    say,
+    state::{ActiveVersion, Components},
+};
+use eyre::{Result, bail};
+use fs_err as fs;
+
+pub(crate) async fn run(config: &Config, command: &ComponentCommand, force: bool) -> Result<()> {
+    match command {
+        ComponentCommand::Add { components } => add(config, components, force).await,
+        ComponentCommand::Remove { components } => remove(config, components, force),
+        ComponentCommand::List => list(config),
+    }
+}
+
+/// Selects additional components, installing them into the active version.
+async fn add(config: &Config, names: &[String], force: bool) -> Result<()> {
+    let added = config.parse_components(names)?;
+
+    let mut config = config.clone();
+    config.components = config
+        .network
+        .bins
+        .iter()
+        .copied()
+        .filter(|bin| config.is_selected(bin) || added.contains(bin))
+        .collect();
+
+    let Some(active) = ActiveVersion::read(&config)? else {
+        Components::write(&config, &config.components)?;
+        say!("selected {}, they will be installed with the next version", added.join(", "));
+        return Ok(());
+    };
+
+    let version_dir = config.version_dir(&active.repo, &active.version);
+    if added.iter().any(|bin| !version_dir.join(install::bin_name(bin)).exists()) {
+        let metadata = VersionMetadata::read(&version_dir)?.unwrap_or_default();
+        if active.repo != config.network.repo || metadata.platform.is_none() {
+            bail!(
+                "{} of {} was not installed from a release, rebuild it to add components",
+                active.version,
+                active.repo
+            );
+        }
+
+        let target = install::installed_target(&metadata)?;
+        let downloader = Downloader::new()?;
+        install::install_release(&config, &downloader, &active.version, &target, force).await?;
+    }
+
+    // Only remember the selection once the components are installed.
+    install::use_version(&config, &active.repo, &active.version, force)?;
+    Components::write(&config, &config.components)
+}
+
+/// Deselects components, removing them from the active version.
+fn remove(config: &Config, names: &[String], force: bool) -> Result<()> {
+    let removed = config.parse_components(names)?;
+
+    let mut config = config.clone();
+    config.components.retain(|bin| !removed.contains(bin));
+    if config.components.is_empty() {
+        bail!("at least one component must remain selected");
+    }
+    Components::write(&config, &config.components)?;
+
+    let Some(active) = ActiveVersion::read(&config)? else {
+        return Ok(());
+    };
+
+    let version_dir = config.version_dir(&active.repo, &active.version);
+    install::prune_components(&config, &version_dir)?;
+    if let Some(mut metadata) = VersionMetadata::read(&version_dir)? {
+        metadata.digests.retain(|bin, _| config.is_selected(bin));
+        metadata.write(&version_dir)?;
+    }
+
+    install::use_version(&config, &active.repo, &active.version, force)?;
+    for bin in removed {
+        say!("removed {bin}");
+    }
+    Ok(())
+}
+
+fn list(config: &Config) -> Result<()> {
+    let version_dir = ActiveVersion::read(config)?
+        .map(|active| config.version_dir(&active.repo, &active.version));
+
+    for bin in config.network.bins {
+        let installed = version_dir
+            .as_ref()
+            .is_some_and(|dir| fs::metadata(dir.join(install::bin_name(bin))).is_ok());
+        let status = match (config.is_selected(bin), installed) {
+            (true, true) => "installed",
+            (true, false) => "selected, not installed",
+            (false, _) => "not selected",
+        };
+        say!("{bin} ({status})");
+    }
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
-use crate::{cli::Network, say};
-use eyre::Result;
+use crate::{cli::Network, say, state::Components};
+use eyre::{Result, bail};
 use fs_err as fs;
 use std::path::{Path, PathBuf};
 
@@ -16,13 +16,15 @@ pub(crate) const LONG_VERSION: &str = concat!(
 
 pub(crate) const FOUNDRYUP_REPO: &str = "foundry-rs/foundryup";
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Config {
     pub foundry_dir: PathBuf,
     pub versions_dir: PathBuf,
     pub bin_dir: PathBuf,
     pub man_dir: PathBuf,
     pub network: NetworkConfig,
+    /// The binaries of the network to install and link, a subset of `network.bins`.
+    pub components: Vec<&'static str>,
 }
 
 impl Config {
@@ -40,8 +42,43 @@ impl Config {
         let bin_dir = foundry_dir.join("bin");
         let man_dir = foundry_dir.join("share/man/man1");
         let network = NetworkConfig::for_network(network);
+        let components = network.bins.to_vec();
 
-        Ok(Self { foundry_dir, versions_dir, bin_dir, man_dir, network })
+        Ok(Self { foundry_dir, versions_dir, bin_dir, man_dir, network, components })
+    }
+
+    /// Selects the components to install and link.
+    ///
+    /// Falls back to the selection persisted by a previous install when none is requested.
+    pub(crate) fn select_components(&mut self, requested: &[String]) -> Result<()> {
+        let requested = if requested.is_empty() {
+            match Components::read(self)? {
+                Some(persisted) => persisted,
+                None => return Ok(()),
+            }
+        } else {
+            requested.to_vec()
+        };
+
+        self.components = self.parse_components(&requested)?;
+        Ok(())
+    }
+
+    /// Parses component names, returning them in the order of `network.bins`.
+    pub(crate) fn parse_components(&self, names: &[String]) -> Result<Vec<&'static str>> {
+        if let Some(unknown) = names.iter().find(|name| !self.network.bins.contains(&name.as_str()))
+        {
+            bail!(
+                "unknown component '{unknown}', expected one of: {}",
+                self.network.bins.join(", ")
+            );
+        }
+
+        Ok(self.network.bins.iter().copied().filter(|bin| names.iter().any(|n| n == bin)).collect())
+    }
+
+    pub(crate) fn is_selected(&self, bin: &str) -> bool {
+        self.components.contains(&bin)
     }
 
     pub(crate) fn ensure_dirs(&self) -> Result<()> {
@@ -110,6 +147,30 @@ impl Config {
         self.versions_dir.join(repo).join(version)
     }
 
+    /// Returns the directory the manpages of an installed version are stored in.
+    pub(crate) fn version_man_dir(&self, repo: &str, version: &str) -> PathBuf {
+        self.version_dir(repo, version).join("share/man/man1")
+    }
+
+    /// Returns the directory data fetched for a repository is cached in.
+    pub(crate) fn cache_dir(&self, repo: &str) -> PathBuf {
+        self.foundry_dir.join("cache").join(repo)
+    }
+
+    /// Returns the git worktree a commit of a repository is checked out in to be built.
+    pub(crate) fn worktree_dir(&self, repo: &str, commit: &str) -> PathBuf {
+        self.worktrees_dir().join(repo).join(commit)
+    }
+
+    pub(crate) fn worktrees_dir(&self) -> PathBuf {
+        self.foundry_dir.join("worktrees")
+    }
+
+    /// Returns the cargo target directory shared by the source builds of a repository.
+    pub(crate) fn build_target_dir(&self, repo: &str) -> PathBuf {
+        self.cache_dir(repo).join("target")
+    }
+
     pub(crate) fn bin_path(&self, name: &str) -> PathBuf {
         let name = if cfg!(windows) && !name.ends_with(".exe") {
             format!("{name}.exe")
@@ -153,6 +214,9 @@ impl NetworkConfig {
         has_attestation: false,
     };
 
+    /// The configurations of all supported networks.
+    pub(crate) const ALL: [Self; 2] = [Self::FOUNDRY, Self::TEMPO];
+
     fn for_network(network: Option<Network>) -> Self {
         match network {
             Some(Network::Tempo) => Self::TEMPO,
@@ -0,0 +1,44 @@
+use crate::{
+    config::Config,
+    git, say,
+    uninstall::{cloned_repos, dir_size, format_size},
+};
+use eyre::Result;
+use fs_err as fs;
+
+/// Removes the git worktrees source builds were checked out in, the logs of failed builds, and the
+/// target directories of builds made in the clones before worktrees were used.
+///
+/// The clones and the shared cargo target directories are kept, so that later builds only fetch
+/// and compile what changed.
+pub(crate) async fn run(config: &Config) -> Result<()> {
+    let worktrees_dir = config.worktrees_dir();
+    let mut size = dir_size(&worktrees_dir);
+    if worktrees_dir.exists() {
+        fs::remove_dir_all(&worktrees_dir)?;
+    }
+
+    let logs_dir = config.foundry_dir.join("logs");
+    size += dir_size(&logs_dir);
+    if logs_dir.exists() {
+        fs::remove_dir_all(&logs_dir)?;
+    }
+
+    for repo in cloned_repos(config)? {
+        git::prune_worktrees(&repo).await?;
+
+        // Builds used to happen in the clone itself, they now share a target directory.
+        let legacy_target = repo.join("target");
+        if legacy_target.is_dir() {
+            size += dir_size(&legacy_target);
+            fs::remove_dir_all(&legacy_target)?;
+        }
+    }
+
+    if size == 0 {
+        say!("nothing to clean up");
+    } else {
+        say!("removed the build worktrees and logs, freeing {}", format_size(size));
+    }
+    Ok(())
+}
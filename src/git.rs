@@ -0,0 +1,153 @@
+use crate::cli::CloneMode;
+use eyre::{Result, WrapErr, bail};
+use std::path::Path;
+use tracing::debug;
+
+/// URL foundryup clones repositories from, `{repo}` is replaced with `<owner>/<name>`.
+pub(crate) const DEFAULT_URL: &str = "https://github.com/{repo}";
+
+/// Returns the URL to clone the repository from.
+pub(crate) fn clone_url(template: &str, repo: &str) -> String {
+    template.replace("{repo}", repo)
+}
+
+/// Clones the repository without checking out any files.
+///
+/// Objects already present in `reference` are copied from it instead of being downloaded.
+pub(crate) async fn clone(
+    url: &str,
+    dest: &Path,
+    mode: CloneMode,
+    reference: Option<&Path>,
+) -> Result<()> {
+    let mut args = vec!["clone".to_string(), "--no-checkout".to_string()];
+    args.extend(mode.args().iter().map(|arg| arg.to_string()));
+    if let Some(reference) = reference {
+        args.push("--reference-if-able".to_string());
+        args.push(reference.display().to_string());
+        args.push("--dissociate".to_string());
+    }
+    args.push(url.to_string());
+    args.push(dest.display().to_string());
+
+    run(None, &args).await
+}
+
+/// Fetches a refspec from `origin`.
+///
+/// A shallow clone only fetches the tip in shallow mode, and gets its complete history in full
+/// mode. Other clones keep their history, as a clone cannot be made shallow or partial afterwards
+/// without discarding it.
+pub(crate) async fn fetch(repo: &Path, refspec: &str, mode: CloneMode) -> Result<()> {
+    let shallow = is_shallow(repo).await?;
+    let mut args = vec!["fetch", "origin"];
+    match mode {
+        CloneMode::Shallow if shallow => args.push("--depth=1"),
+        CloneMode::Full if shallow => args.push("--unshallow"),
+        _ => {}
+    }
+    args.push(refspec);
+    run(Some(repo), &args).await
+}
+
+/// Points `origin` at the URL, in case it changed since the repository was cloned.
+pub(crate) async fn set_origin(repo: &Path, url: &str) -> Result<()> {
+    run(Some(repo), &["remote", "set-url", "origin", url]).await
+}
+
+/// Checks out the commit in a detached worktree at `dir`, unless it already is.
+pub(crate) async fn add_worktree(repo: &Path, dir: &Path, commit: &str) -> Result<()> {
+    if dir.join(".git").exists() {
+        return Ok(());
+    }
+    // Forget worktrees whose directory was removed.
+    prune_worktrees(repo).await?;
+
+    let dir = dir.display().to_string();
+    run(Some(repo), &["worktree", "add", "--quiet", "--detach", &dir, commit]).await
+}
+
+/// Removes the metadata of worktrees whose directory no longer exists.
+pub(crate) async fn prune_worktrees(repo: &Path) -> Result<()> {
+    run(Some(repo), &["worktree", "prune"]).await
+}
+
+/// Returns the commit a ref of a remote repository points at, without fetching it.
+pub(crate) async fn ls_remote(url: &str, git_ref: &str) -> Result<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["ls-remote", url, git_ref, &format!("{git_ref}^{{}}")])
+        .output()
+        .await
+        .wrap_err("failed to run git")?;
+    if !output.status.success() {
+        bail!("git ls-remote failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let refs: Vec<(&str, &str)> = stdout.lines().filter_map(|line| line.split_once('\t')).collect();
+    // Annotated tags are listed twice, `<ref>^{}` being the commit they point at.
+    let peeled = format!("{git_ref}^{{}}");
+    let commit = refs
+        .iter()
+        .find(|(_, name)| *name == peeled)
+        .or_else(|| refs.iter().find(|(_, name)| *name == git_ref))
+        .or_else(|| refs.first())
+        .map(|(commit, _)| commit.to_string());
+    commit.ok_or_else(|| eyre::eyre!("{git_ref} was not found in {url}"))
+}
+
+/// Returns the content of a file at a commit, if it exists.
+pub(crate) async fn show(repo: &Path, commit: &str, path: &str) -> Option<Vec<u8>> {
+    let output = tokio::process::Command::new("git")
+        .args(["show", &format!("{commit}:{path}")])
+        .current_dir(repo)
+        .output()
+        .await
+        .ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+/// Returns the commit SHA a revision resolves to.
+pub(crate) async fn rev_parse(repo: &Path, rev: &str) -> Result<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["rev-parse", "--verify", &format!("{rev}^{{commit}}")])
+        .current_dir(repo)
+        .output()
+        .await
+        .wrap_err("failed to run git")?;
+    if !output.status.success() {
+        bail!("could not resolve {rev} to a commit");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Returns whether the repository is a shallow clone.
+async fn is_shallow(repo: &Path) -> Result<bool> {
+    let output = tokio::process::Command::new("git")
+        .args(["rev-parse", "--is-shallow-repository"])
+        .current_dir(repo)
+        .output()
+        .await
+        .wrap_err("failed to run git")?;
+    if !output.status.success() {
+        bail!("git rev-parse failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim() == "true")
+}
+
+/// Runs a git command, failing if it does not succeed.
+async fn run(dir: Option<&Path>, args: &[impl AsRef<std::ffi::OsStr>]) -> Result<()> {
+    let mut cmd = tokio::process::Command::new("git");
+    cmd.args(args);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    debug!("running {cmd:?}");
+
+    let subcommand = args[0].as_ref().to_string_lossy().into_owned();
+    let status = cmd.status().await.wrap_err("failed to run git")?;
+    if !status.success() {
+        bail!("git {subcommand} failed");
+    }
+    Ok(())
+}
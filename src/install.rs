@@ -1,24 +1,44 @@
 use crate::{
-    cli::Cli,
+    build,
+    cli::{Cli, CloneMode, NightliesArgs},
     config::Config,
     download::{Downloader, compute_sha256, extract_tar_gz, extract_zip},
-    platform::{Platform, Target},
-    say, warn,
+    git,
+    metadata::{BuildSettings, ChannelAliases, TagCache, VersionMetadata},
+    path,
+    platform::{Arch, Platform, Target},
+    releases::{self, NightlyRequest},
+    say,
+    state::{Activation, ActiveVersion, Components},
+    warn,
 };
 use eyre::{Result, WrapErr, bail};
 use fs_err as fs;
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tracing::debug;
 
 pub(crate) async fn run(config: &Config, args: &Cli) -> Result<()> {
     config.ensure_dirs()?;
 
+    if !args.components.is_empty() {
+        Components::write(config, &config.components)?;
+    }
+
     if let Some(ref local_path) = args.path {
         return install_from_local(config, local_path, args).await;
     }
 
     let repo = args.repo.as_deref().unwrap_or(config.network.repo);
 
-    let should_build = args.branch.is_some() || args.pr.is_some() || args.commit.is_some();
+    let should_build = args.branch.is_some()
+        || args.pr.is_some()
+        || args.tag.is_some()
+        || args.git_ref.is_some()
+        || args.commit.is_some();
     let is_default_repo = repo == config.network.repo;
 
     if is_default_repo && !should_build {
@@ -29,41 +49,126 @@ pub(crate) async fn run(config: &Config, args: &Cli) -> Result<()> {
 }
 
 async fn install_prebuilt(config: &Config, args: &Cli) -> Result<()> {
-    let (version, tag) =
-        normalize_version(args.version.as_deref().unwrap_or(config.network.default_version));
-
     let repo = config.network.repo;
-
-    say!("installing {} (version {version}, tag {tag})", config.network.display_name);
+    let requested = args.version.as_deref().unwrap_or(config.network.default_version);
 
     let target = Target::detect(args.platform.as_deref(), args.arch.as_deref())?;
     let downloader = Downloader::new()?;
 
+    let requested = if let Some(request) = NightlyRequest::parse(requested) {
+        let tag = releases::resolve_nightly(&downloader, repo, request).await?;
+        say!("resolved {requested} to {tag}");
+        tag
+    } else if let Some(req) = releases::parse_requirement(requested) {
+        let releases = releases::fetch_releases(&downloader, repo, |_| false).await?;
+        TagCache::extend(&config.cache_dir(repo), releases.iter().map(|r| r.tag.as_str()))?;
+        // Only consider releases that can be installed on this platform.
+        let archive_suffix = format!(
+            "_{}_{}.{}",
+            target.platform.as_str(),
+            target.arch.as_str(),
+            target.platform.archive_ext()
+        );
+        let installable = releases.iter().filter(|release| {
+            release.assets.iter().any(|asset| {
+                asset.starts_with(config.network.archive_prefix) && asset.ends_with(&archive_suffix)
+            })
+        });
+        let Some(tag) = releases::select_version(&req, installable.map(|r| r.tag.as_str())) else {
+            bail!("no release of {repo} for {} matches {requested}", target.platform.as_str());
+        };
+        say!("resolved {requested} to {tag}");
+        tag.to_string()
+    } else {
+        requested.to_string()
+    };
+    let requested = requested.as_str();
+
+    let channel = releases::is_channel(requested).then_some(requested);
+    let resolved = match channel {
+        Some(channel) => match releases::resolve_channel(&downloader, repo, channel).await {
+            Ok(resolved) => {
+                say!("resolved {channel} to {resolved}");
+                TagCache::extend(&config.cache_dir(repo), [resolved.as_str()])?;
+                resolved
+            }
+            Err(e) => {
+                warn!("could not resolve {channel} to a release, installing it as is: {e}");
+                channel.to_string()
+            }
+        },
+        None => requested.to_string(),
+    };
+    let tag = install_release(config, &downloader, &resolved, &target, args.force).await?;
+
+    record_channel(config, repo, channel, &tag)?;
+    activate(config, repo, &tag, "release")?;
+    say!("done!");
+
+    Ok(())
+}
+
+/// Downloads, verifies and extracts a release into its version directory, without activating it.
+///
+/// Returns the release tag, which is also the name of the version directory.
+pub(crate) async fn install_release(
+    config: &Config,
+    downloader: &Downloader,
+    release: &str,
+    target: &Target,
+    force: bool,
+) -> Result<String> {
+    let repo = config.network.repo;
+    let (version, tag) = normalize_version(release);
+
+    say!("installing {} (version {version}, tag {tag})", config.network.display_name);
+
     let release_url =
         format!("https://github.com/{}/releases/download/{tag}/", config.network.repo);
 
-    let hashes = if config.network.has_attestation && !args.force {
-        fetch_and_verify_attestation(config, repo, &downloader, &release_url, &version, &target)
-            .await?
-    } else if args.force {
+    let hashes = if config.network.has_attestation && !force {
+        let bins = config.components.join(", ");
+        say!("checking if {bins} for {version} version are already installed");
+        fetch_attestation(downloader, &release_url, &version, target).await?
+    } else if force {
         say!("skipped SHA verification due to --force flag");
         None
     } else {
         None
     };
 
-    download_and_extract(config, repo, &downloader, &release_url, &version, &tag, &target).await?;
+    if let Some(ref hashes) = hashes {
+        if installed_binaries_match(config, repo, &tag, hashes)? {
+            say!("version {version} already installed and verified");
+            prune_components(config, &config.version_dir(repo, &tag))?;
+            record_digests(config, repo, &tag, Some(hashes), Some(target))?;
+            return Ok(tag);
+        }
+        say!("binaries not found or do not match expected hashes, downloading new binaries");
+    }
+
+    download_and_extract(config, repo, downloader, &release_url, &version, &tag, target).await?;
 
     if let Some(ref hashes) = hashes {
         verify_installed_binaries(config, repo, &tag, hashes)?;
     }
+    prune_components(config, &config.version_dir(repo, &tag))?;
+    record_digests(config, repo, &tag, hashes.as_ref(), Some(target))?;
 
-    download_manpages(config, &downloader, &release_url, &version).await;
+    let man_dir = config.version_man_dir(repo, &tag);
+    download_manpages(config, downloader, &release_url, &version, &man_dir).await;
 
-    use_version(config, repo, &tag)?;
-    say!("done!");
+    Ok(tag)
+}
 
-    Ok(())
+/// Points the channel the version was resolved from at the installed version.
+fn record_channel(config: &Config, repo: &str, channel: Option<&str>, tag: &str) -> Result<()> {
+    match channel {
+        Some(channel) if channel != tag => {
+            ChannelAliases::read(&config.versions_dir.join(repo))?.set(channel, tag)
+        }
+        _ => Ok(()),
+    }
 }
 
 async fn install_from_local(config: &Config, local_path: &Path, args: &Cli) -> Result<()> {
@@ -79,7 +184,7 @@ async fn install_from_local(config: &Config, local_path: &Path, args: &Cli) -> R
         .arg("--profile")
         .arg(&args.cargo_profile)
         .current_dir(local_path);
-    cmd.env("RUSTFLAGS", rustflags());
+    cmd.env("RUSTFLAGS", rustflags(args.portable));
 
     if let Some(jobs) = args.jobs {
         cmd.arg("--jobs").arg(jobs.to_string());
@@ -88,10 +193,7 @@ async fn install_from_local(config: &Config, local_path: &Path, args: &Cli) -> R
         cmd.arg("--features").arg(features);
     }
 
-    let status = cmd.status().await.wrap_err("failed to run cargo build")?;
-    if !status.success() {
-        bail!("cargo build failed");
-    }
+    build::cargo_build(config, cmd, "local").await?;
 
     let target_dir = profile_target_dir(&args.cargo_profile);
     for bin in config.network.bins {
@@ -101,6 +203,9 @@ async fn install_from_local(config: &Config, local_path: &Path, args: &Cli) -> R
         if dest.exists() {
             fs::remove_file(&dest)?;
         }
+        if !config.is_selected(bin) {
+            continue;
+        }
 
         #[cfg(unix)]
         std::os::unix::fs::symlink(&src, &dest)?;
@@ -123,72 +228,95 @@ async fn install_from_source(config: &Config, repo: &str, args: &Cli) -> Result<
         args.branch.clone().unwrap_or_else(|| "master".to_string())
     };
 
-    let repo_path = config.repo_dir(repo);
-    let author = repo.split('/').next().unwrap_or(repo);
+    // The refspec to fetch and the revision to check out afterwards.
+    let (refspec, rev) = if let Some(ref tag) = args.tag {
+        (format!("+refs/tags/{tag}:refs/tags/{tag}"), format!("refs/tags/{tag}"))
+    } else if let Some(ref git_ref) = args.git_ref {
+        (git_ref.clone(), "FETCH_HEAD".to_string())
+    } else {
+        (format!("+{branch}:refs/remotes/origin/{branch}"), format!("origin/{branch}"))
+    };
+    let fetched = args.tag.as_deref().or(args.git_ref.as_deref()).unwrap_or(&branch);
 
-    if !repo_path.exists() {
-        let author_dir = config.foundry_dir.join(author);
-        fs::create_dir_all(&author_dir)?;
+    let repo_path = config.repo_dir(repo);
+    let url = git::clone_url(&args.git_url, repo);
+    let mut settings = BuildSettings {
+        profile: args.cargo_profile.clone(),
+        features: args.cargo_features.clone(),
+        rustflags: rustflags(args.portable),
+        rustc: String::new(),
+    };
 
-        say!("cloning {repo}...");
-        let status = tokio::process::Command::new("git")
-            .args(["clone", &format!("https://github.com/{repo}")])
-            .current_dir(&author_dir)
-            .status()
-            .await?;
-        if !status.success() {
-            bail!("git clone failed");
+    // Resolve the commit without fetching anything, in case it was built before.
+    let requested = match args.commit {
+        Some(ref commit) => commit.clone(),
+        None => {
+            let remote_ref = if let Some(ref tag) = args.tag {
+                format!("refs/tags/{tag}")
+            } else if let Some(ref git_ref) = args.git_ref {
+                git_ref.clone()
+            } else if branch.starts_with("refs/") {
+                branch.clone()
+            } else {
+                format!("refs/heads/{branch}")
+            };
+            let commit = git::ls_remote(&url, &remote_ref).await?;
+            say!("resolved {fetched} to {commit}");
+            commit
+        }
+    };
+    // A commit that was built before is in the clone, along with the toolchain it pins.
+    if repo_path.exists() && git::rev_parse(&repo_path, &requested).await.is_ok() {
+        settings.rustc = pinned_rustc_version(&repo_path, &requested).await?;
+        if let Some(version) = find_build(config, repo, &requested, &settings)? {
+            let version_dir = config.version_dir(repo, &version);
+            match check_recorded_digests(config, &version_dir, &version, args.force) {
+                Ok(()) => {
+                    say!("{version} was already built from {requested}, activating it");
+                    activate(config, repo, &version, "source")?;
+                    say!("done");
+                    return Ok(());
+                }
+                Err(e) => warn!("not reusing {version}: {e}"),
+            }
         }
     }
 
-    say!("fetching {branch}...");
-    let status = tokio::process::Command::new("git")
-        .args(["fetch", "origin", &format!("{branch}:remotes/origin/{branch}")])
-        .current_dir(&repo_path)
-        .status()
-        .await?;
-    if !status.success() {
-        bail!("git fetch failed");
-    }
+    if !repo_path.exists() {
+        // Forks share most of their history with the upstream repository.
+        let upstream = config.repo_dir(config.network.repo);
+        let reference = args
+            .git_reference
+            .as_deref()
+            .or_else(|| (repo != config.network.repo && upstream.exists()).then_some(&upstream));
 
-    let status = tokio::process::Command::new("git")
-        .args(["checkout", &format!("origin/{branch}")])
-        .current_dir(&repo_path)
-        .status()
-        .await?;
-    if !status.success() {
-        bail!("git checkout failed");
+        say!("cloning {repo}...");
+        git::clone(&url, &repo_path, args.clone, reference).await?;
+    } else {
+        git::set_origin(&repo_path, &url).await?;
     }
 
-    if let Some(ref commit) = args.commit {
-        let status = tokio::process::Command::new("git")
-            .args(["checkout", commit])
-            .current_dir(&repo_path)
-            .status()
-            .await?;
-        if !status.success() {
-            bail!("git checkout commit failed");
-        }
+    say!("fetching {fetched}...");
+    git::fetch(&repo_path, &refspec, args.clone).await?;
+    if let Some(ref commit) = args.commit
+        && args.clone == CloneMode::Shallow
+    {
+        git::fetch(&repo_path, commit, args.clone).await?;
     }
+    let commit = git::rev_parse(&repo_path, args.commit.as_deref().unwrap_or(&rev)).await?;
 
-    let version = if let Some(ref commit) = args.commit {
-        format!("{author}-commit-{commit}")
-    } else if let Some(pr) = args.pr {
-        format!("{author}-pr-{pr}")
-    } else {
-        let normalized_branch = branch.replace('/', "-");
-        format!("{author}-branch-{normalized_branch}")
-    };
+    let version = source_version_name(repo, args, &branch, &commit);
+    say!("installing version {version} (commit {commit})");
 
-    say!("installing version {version}");
+    // Each commit is built in its own worktree, sharing the target directory between them.
+    let worktree = config.worktree_dir(repo, &commit);
+    git::add_worktree(&repo_path, &worktree, &commit).await?;
+    let build_dir = config.build_target_dir(repo);
+    settings.rustc = rustc_version(&worktree).await?;
 
     let mut cmd = tokio::process::Command::new("cargo");
-    cmd.arg("build")
-        .arg("--bins")
-        .arg("--profile")
-        .arg(&args.cargo_profile)
-        .current_dir(&repo_path);
-    cmd.env("RUSTFLAGS", rustflags());
+    cmd.arg("build").arg("--bins").arg("--profile").arg(&args.cargo_profile).current_dir(&worktree);
+    cmd.env("RUSTFLAGS", &settings.rustflags).env("CARGO_TARGET_DIR", &build_dir);
 
     if let Some(jobs) = args.jobs {
         cmd.arg("--jobs").arg(jobs.to_string());
@@ -197,58 +325,90 @@ async fn install_from_source(config: &Config, repo: &str, args: &Cli) -> Result<
         cmd.arg("--features").arg(features);
     }
 
-    let status = cmd.status().await.wrap_err("failed to run cargo build")?;
-    if !status.success() {
-        bail!("cargo build failed");
-    }
+    build::cargo_build(config, cmd, &version).await?;
 
     let version_dir = config.version_dir(repo, &version);
     fs::create_dir_all(&version_dir)?;
 
     let target_dir = profile_target_dir(&args.cargo_profile);
-    for bin in config.network.bins {
-        let src = repo_path.join("target").join(target_dir).join(bin_name(bin));
+    for bin in &config.components {
+        let src = build_dir.join(target_dir).join(bin_name(bin));
         if src.exists() {
-            fs::rename(&src, version_dir.join(bin_name(bin)))?;
+            fs::copy(&src, version_dir.join(bin_name(bin)))?;
         }
     }
 
-    use_version(config, repo, &version)?;
+    record_digests(config, repo, &version, None, None)?;
+    let mut metadata = VersionMetadata::read(&version_dir)?.unwrap_or_default();
+    metadata.commit = Some(commit);
+    metadata.build = Some(settings);
+    metadata.write(&version_dir)?;
+    activate(config, repo, &version, "source")?;
     say!("done");
 
     Ok(())
 }
 
-async fn fetch_and_verify_attestation(
+/// Returns the name of a source build, including the commit for branches and other moving refs.
+fn source_version_name(repo: &str, args: &Cli, branch: &str, commit: &str) -> String {
+    let author = repo.split('/').next().unwrap_or(repo);
+    let short = &commit[..commit.len().min(7)];
+
+    if let Some(ref commit) = args.commit {
+        format!("{author}-commit-{commit}")
+    } else if let Some(pr) = args.pr {
+        format!("{author}-pr-{pr}-{short}")
+    } else if let Some(ref tag) = args.tag {
+        format!("{author}-tag-{}", tag.replace('/', "-"))
+    } else if let Some(ref git_ref) = args.git_ref {
+        let git_ref = git_ref.strip_prefix("refs/").unwrap_or(git_ref);
+        format!("{author}-ref-{}-{short}", git_ref.replace('/', "-"))
+    } else {
+        let normalized_branch = branch.replace('/', "-");
+        format!("{author}-branch-{normalized_branch}-{short}")
+    }
+}
+
+/// Returns an installed version of the repository built from the commit with the same settings.
+///
+/// `commit` may be abbreviated.
+fn find_build(
     config: &Config,
     repo: &str,
+    commit: &str,
+    settings: &BuildSettings,
+) -> Result<Option<String>> {
+    for (installed_repo, version) in installed_versions(config)? {
+        if installed_repo != repo {
+            continue;
+        }
+        let Some(metadata) = VersionMetadata::read(&config.version_dir(repo, &version))? else {
+            continue;
+        };
+        if metadata.commit.is_some_and(|built| built.starts_with(commit))
+            && metadata.build.as_ref() == Some(settings)
+        {
+            return Ok(Some(version));
+        }
+    }
+    Ok(None)
+}
+
+pub(crate) async fn fetch_attestation(
     downloader: &Downloader,
     release_url: &str,
     version: &str,
     target: &Target,
 ) -> Result<Option<HashMap<String, String>>> {
-    let bins = config.network.bins;
-    say!("checking if {} for {version} version are already installed", bins.join(", "));
-
     let attestation_url = format!(
         "{release_url}foundry_{version}_{platform}_{arch}.attestation.txt",
         platform = target.platform.as_str(),
         arch = target.arch.as_str()
     );
 
-    let attestation_link = match downloader.download_to_string(&attestation_url).await {
-        Ok(content) => {
-            let link = content.lines().next().unwrap_or("").trim().to_string();
-            if link.is_empty() || link.contains("Not Found") {
-                say!("no attestation found for this release, skipping SHA verification");
-                return Ok(None);
-            }
-            link
-        }
-        Err(_) => {
-            say!("no attestation found for this release, skipping SHA verification");
-            return Ok(None);
-        }
+    let Some(attestation_link) = fetch_attestation_link(downloader, &attestation_url).await? else {
+        say!("no attestation found for this release, skipping SHA verification");
+        return Ok(None);
     };
 
     say!("found attestation for {version} version, downloading attestation artifact, checking...");
@@ -256,45 +416,73 @@ async fn fetch_and_verify_attestation(
     let artifact_url = format!("{attestation_link}/download");
     let artifact_json = downloader.download_to_string(&artifact_url).await?;
 
-    let hashes = parse_attestation_payload(&artifact_json)?;
+    parse_attestation_payload(&artifact_json).map(Some)
+}
 
-    let version_dir = config.version_dir(repo, version);
+/// Prefix of the links to the Sigstore bundles of attestations.
+const ATTESTATION_HOST: &str = "https://github.com/";
 
-    if version_dir.exists() {
-        let mut all_match = true;
-        for bin in bins {
-            let bin_name = bin_name(bin);
-            let expected = hashes.get(*bin).or_else(|| hashes.get(&bin_name));
-            let path = version_dir.join(&bin_name);
-
-            match expected {
-                Some(expected_hash) if path.exists() => {
-                    let actual = compute_sha256(&path)?;
-                    if actual != *expected_hash {
-                        all_match = false;
-                        break;
-                    }
-                }
-                _ => {
-                    all_match = false;
-                    break;
+/// Returns the link to the Sigstore bundle an attestation file points at, if the release has one.
+///
+/// Links to other hosts than GitHub are rejected, as the bundle's signature is not verified.
+pub(crate) async fn fetch_attestation_link(
+    downloader: &Downloader,
+    attestation_url: &str,
+) -> Result<Option<String>> {
+    let Ok(content) = downloader.download_to_string(attestation_url).await else {
+        return Ok(None);
+    };
+    parse_attestation_link(&content)
+        .wrap_err_with(|| format!("invalid attestation {attestation_url}"))
+}
+
+fn parse_attestation_link(content: &str) -> Result<Option<String>> {
+    let link = content.lines().next().unwrap_or("").trim();
+    if link.is_empty() || link.contains("Not Found") {
+        return Ok(None);
+    }
+    if !link.starts_with(ATTESTATION_HOST) {
+        bail!("{link} is not hosted on {ATTESTATION_HOST}");
+    }
+    Ok(Some(link.to_string()))
+}
+
+/// Returns whether every binary of the given version is installed and matches the attestation.
+fn installed_binaries_match(
+    config: &Config,
+    repo: &str,
+    tag: &str,
+    hashes: &HashMap<String, String>,
+) -> Result<bool> {
+    let version_dir = config.version_dir(repo, tag);
+    if !version_dir.exists() {
+        return Ok(false);
+    }
+
+    for bin in &config.components {
+        let bin_name = bin_name(bin);
+        let expected = hashes.get(*bin).or_else(|| hashes.get(&bin_name));
+        let path = version_dir.join(&bin_name);
+
+        match expected {
+            Some(expected_hash) if path.exists() => {
+                if compute_sha256(&path)? != *expected_hash {
+                    return Ok(false);
                 }
             }
-        }
-
-        if all_match {
-            say!("version {version} already installed and verified, activating...");
-            use_version(config, repo, version)?;
-            say!("done!");
-            std::process::exit(0);
+            _ => return Ok(false),
         }
     }
 
-    say!("binaries not found or do not match expected hashes, downloading new binaries");
-    Ok(Some(hashes))
+    Ok(true)
 }
 
 fn parse_attestation_payload(json: &str) -> Result<HashMap<String, String>> {
+    Ok(statement_digests(&parse_attestation_statement(json)?))
+}
+
+/// Decodes the in-toto statement enveloped in a Sigstore bundle.
+pub(crate) fn parse_attestation_statement(json: &str) -> Result<serde_json::Value> {
     let parsed: serde_json::Value = serde_json::from_str(json)?;
     let payload_b64 = parsed["dsseEnvelope"]["payload"]
         .as_str()
@@ -302,11 +490,14 @@ fn parse_attestation_payload(json: &str) -> Result<HashMap<String, String>> {
 
     let payload_bytes =
         base64::Engine::decode(&base64::engine::general_purpose::STANDARD, payload_b64)?;
-    let payload_json: serde_json::Value = serde_json::from_slice(&payload_bytes)?;
+    Ok(serde_json::from_slice(&payload_bytes)?)
+}
 
+/// Returns the SHA-256 digests of the subjects of an in-toto statement, keyed by name.
+pub(crate) fn statement_digests(statement: &serde_json::Value) -> HashMap<String, String> {
     let mut hashes = HashMap::new();
 
-    if let Some(subject) = payload_json["subject"].as_array() {
+    if let Some(subject) = statement["subject"].as_array() {
         for entry in subject {
             if let (Some(name), Some(digest)) =
                 (entry["name"].as_str(), entry["digest"]["sha256"].as_str())
@@ -316,7 +507,7 @@ fn parse_attestation_payload(json: &str) -> Result<HashMap<String, String>> {
         }
     }
 
-    Ok(hashes)
+    hashes
 }
 
 async fn download_and_extract(
@@ -379,48 +570,200 @@ fn verify_installed_binaries(
     let version_dir = config.version_dir(repo, tag);
     let mut failed = false;
 
-    for bin in config.network.bins {
-        let bin_name = bin_name(bin);
-        let expected = hashes.get(*bin).or_else(|| hashes.get(&bin_name));
-        let path = version_dir.join(&bin_name);
-
-        match expected {
-            None => {
+    for check in check_binaries(config, &version_dir, hashes)? {
+        let bin = check.bin;
+        match check.status {
+            BinaryStatus::Verified => say!("{bin} verified ✓"),
+            BinaryStatus::Unverified => {
                 say!("no expected hash for {bin}");
                 failed = true;
             }
-            Some(expected_hash) => {
-                if !path.exists() {
-                    say!("binary {bin} not found at {}", path.display());
-                    failed = true;
-                    continue;
-                }
+            BinaryStatus::Missing => {
+                say!("binary {bin} not found at {}", check.path.display());
+                failed = true;
+            }
+            BinaryStatus::Modified { ref actual } => {
+                say!("{bin} hash verification failed:");
+                say!("  expected: {}", check.expected.as_deref().unwrap_or_default());
+                say!("  actual:   {actual}");
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        bail!("one or more binaries failed post-installation verification");
+    }
 
+    Ok(())
+}
+
+/// The result of comparing an installed binary against its expected digest.
+#[derive(Debug)]
+pub(crate) struct BinaryCheck {
+    pub bin: &'static str,
+    pub path: PathBuf,
+    pub expected: Option<String>,
+    pub status: BinaryStatus,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BinaryStatus {
+    /// The binary matches the expected digest.
+    Verified,
+    /// The binary does not match the expected digest.
+    Modified { actual: String },
+    /// The binary has an expected digest but is not installed.
+    Missing,
+    /// There is no expected digest for the binary.
+    Unverified,
+}
+
+/// Compares the binaries in a version directory against the expected digests.
+pub(crate) fn check_binaries(
+    config: &Config,
+    version_dir: &Path,
+    hashes: &HashMap<String, String>,
+) -> Result<Vec<BinaryCheck>> {
+    let mut checks = Vec::with_capacity(config.network.bins.len());
+
+    for bin in config.network.bins {
+        let bin_name = bin_name(bin);
+        let expected = hashes.get(*bin).or_else(|| hashes.get(&bin_name)).cloned();
+        let path = version_dir.join(&bin_name);
+
+        let status = match expected {
+            None => BinaryStatus::Unverified,
+            Some(_) if !path.exists() => BinaryStatus::Missing,
+            Some(ref expected) => {
                 let actual = compute_sha256(&path)?;
-                if actual != *expected_hash {
-                    say!("{bin} hash verification failed:");
-                    say!("  expected: {expected_hash}");
-                    say!("  actual:   {actual}");
-                    failed = true;
+                if actual == *expected {
+                    BinaryStatus::Verified
                 } else {
-                    say!("{bin} verified ✓");
+                    BinaryStatus::Modified { actual }
                 }
             }
+        };
+
+        checks.push(BinaryCheck { bin, path, expected, status });
+    }
+
+    Ok(checks)
+}
+
+/// Records the digests of the installed binaries so that they can be checked on activation.
+///
+/// Digests from the attestation are used when available, otherwise they are computed locally.
+fn record_digests(
+    config: &Config,
+    repo: &str,
+    tag: &str,
+    hashes: Option<&HashMap<String, String>>,
+    target: Option<&Target>,
+) -> Result<()> {
+    let version_dir = config.version_dir(repo, tag);
+    let mut metadata = VersionMetadata::read(&version_dir).ok().flatten().unwrap_or_default();
+
+    metadata.digests.clear();
+    for bin in config.network.bins {
+        let bin_name = bin_name(bin);
+        let path = version_dir.join(&bin_name);
+        if !path.exists() {
+            continue;
         }
+
+        let digest = match hashes.and_then(|h| h.get(*bin).or_else(|| h.get(&bin_name))) {
+            Some(digest) => digest.clone(),
+            None => compute_sha256(&path)?,
+        };
+        metadata.digests.insert(bin.to_string(), digest);
+    }
+    metadata.digest_source =
+        Some(if hashes.is_some() { "attestation" } else { "local" }.to_string());
+    if let Some(target) = target {
+        metadata.platform = Some(target.platform.as_str().to_string());
+        metadata.arch = Some(target.arch.as_str().to_string());
     }
 
-    if failed {
-        bail!("one or more binaries failed post-installation verification");
+    metadata.write(&version_dir)
+}
+
+/// Removes the binaries of components that are not selected from a version directory.
+pub(crate) fn prune_components(config: &Config, version_dir: &Path) -> Result<()> {
+    for bin in config.network.bins {
+        let path = version_dir.join(bin_name(bin));
+        if !config.is_selected(bin) && path.exists() {
+            debug!("removing unselected component {bin}");
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the target an installed release was downloaded for.
+pub(crate) fn installed_target(metadata: &VersionMetadata) -> Result<Target> {
+    let mut target = Target::detect(None, None)?;
+    if let Some(ref platform) = metadata.platform {
+        target.platform = Platform::from_str(platform)?;
+    }
+    if let Some(ref arch) = metadata.arch {
+        target.arch = Arch::from_str(arch)?;
+    }
+    Ok(target)
+}
+
+/// Re-hashes the binaries of an installed version and compares them against the digests
+/// recorded at install time.
+fn check_recorded_digests(
+    config: &Config,
+    version_dir: &Path,
+    version: &str,
+    force: bool,
+) -> Result<()> {
+    let Some(metadata) = VersionMetadata::read(version_dir)? else {
+        warn!("no recorded digests for version {version}, skipping integrity check");
+        return Ok(());
+    };
+
+    let mut modified = false;
+    for check in check_binaries(config, version_dir, &metadata.digests)? {
+        let bin = check.bin;
+        match check.status {
+            BinaryStatus::Verified | BinaryStatus::Unverified => {}
+            BinaryStatus::Missing if !config.is_selected(bin) => {}
+            BinaryStatus::Missing => {
+                say!("{bin} is missing from {}", version_dir.display());
+                modified = true;
+            }
+            BinaryStatus::Modified { ref actual } => {
+                say!("{bin} was modified since installation:");
+                say!("  expected: {}", check.expected.as_deref().unwrap_or_default());
+                say!("  actual:   {actual}");
+                modified = true;
+            }
+        }
+    }
+
+    if modified {
+        if !force {
+            bail!(
+                "binaries of version {version} do not match the digests recorded at install time; \
+                 reinstall it, or pass --force to activate it anyway (INSECURE)"
+            );
+        }
+        warn!("activating modified binaries of version {version} due to --force flag");
     }
 
     Ok(())
 }
 
+/// Downloads the manpages of a release into the given directory, warning on failure.
 async fn download_manpages(
     config: &Config,
     downloader: &Downloader,
     release_url: &str,
     version: &str,
+    man_dir: &Path,
 ) {
     let man_url = format!(
         "{release_url}{prefix}_man_{version}.tar.gz",
@@ -442,60 +785,155 @@ async fn download_manpages(
         return;
     }
 
-    if let Err(e) = extract_tar_gz(&archive_path, &config.man_dir) {
+    if let Err(e) = fs::create_dir_all(man_dir) {
+        warn!("skipping manpage download: {e}");
+        return;
+    }
+    if let Err(e) = extract_tar_gz(&archive_path, man_dir) {
         warn!("skipping manpage download: {e}");
     }
 }
 
-pub(crate) fn list(config: &Config) -> Result<()> {
-    let bins = config.network.bins;
+/// Returns the manpages of an installed version that belong to the selected components.
+fn manpages(config: &Config, repo: &str, version: &str) -> Result<Vec<PathBuf>> {
+    let man_dir = config.version_man_dir(repo, version);
+    if !man_dir.exists() {
+        return Ok(Vec::new());
+    }
 
-    if config.versions_dir.exists() {
-        for owner_entry in fs::read_dir(&config.versions_dir)? {
-            let owner_entry = owner_entry?;
-            let owner_path = owner_entry.path();
-            if !owner_path.is_dir() {
-                continue;
-            }
+    let mut pages = Vec::new();
+    for entry in fs::read_dir(&man_dir)? {
+        let path = entry?.path();
+        if is_manpage_of(&path, &config.components) {
+            pages.push(path);
+        }
+    }
+    pages.sort();
+    Ok(pages)
+}
 
-            let owner_name = owner_entry.file_name();
-            let owner_name = owner_name.to_string_lossy();
+/// Returns whether a manpage documents one of the given binaries, e.g. `forge-build.1` for forge.
+fn is_manpage_of(path: &Path, bins: &[&str]) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else { return false };
+    bins.iter().any(|bin| name.strip_prefix(bin).is_some_and(|rest| rest.starts_with(['.', '-'])))
+}
 
-            for repo_entry in fs::read_dir(&owner_path)? {
-                let repo_entry = repo_entry?;
-                let repo_path = repo_entry.path();
-                if !repo_path.is_dir() {
-                    continue;
-                }
+/// Moves manpages installed directly into the shared man directory, before they were stored per
+/// version, to the version that is still active.
+fn migrate_manpages(config: &Config) -> Result<()> {
+    if !config.man_dir.exists() {
+        return Ok(());
+    }
+    let Some(active) = ActiveVersion::read(config)? else { return Ok(()) };
+    if !config.version_dir(&active.repo, &active.version).exists() {
+        return Ok(());
+    }
 
-                let repo_name = repo_entry.file_name();
-                let repo_name = repo_name.to_string_lossy();
+    let version_man_dir = config.version_man_dir(&active.repo, &active.version);
+    for entry in fs::read_dir(&config.man_dir)? {
+        let path = entry?.path();
+        if !path.symlink_metadata().is_ok_and(|m| m.is_file()) {
+            continue;
+        }
 
-                for version_entry in fs::read_dir(&repo_path)? {
-                    let version_entry = version_entry?;
-                    let version_path = version_entry.path();
-                    if !version_path.is_dir() {
-                        continue;
-                    }
+        let dest = version_man_dir.join(path.file_name().unwrap_or_default());
+        if dest.exists() {
+            fs::remove_file(&path)?;
+        } else {
+            fs::create_dir_all(&version_man_dir)?;
+            fs::rename(&path, &dest)?;
+        }
+    }
+    Ok(())
+}
 
-                    let version_name = version_entry.file_name();
-                    let version_name = version_name.to_string_lossy();
+/// Replaces the manpages in the shared man directory with the ones of the given version.
+///
+/// Only the links (or copies) of manpages stored per version are replaced. Without symlinks, the
+/// copies are told apart from other manpages by the binaries they document.
+fn link_manpages(config: &Config, repo: &str, version: &str) -> Result<()> {
+    for entry in fs::read_dir(&config.man_dir)? {
+        let path = entry?.path();
+        let linked = if cfg!(unix) {
+            path.symlink_metadata().is_ok_and(|m| m.is_symlink())
+        } else {
+            path.is_file() && is_manpage_of(&path, config.network.bins)
+        };
+        if linked {
+            fs::remove_file(&path)?;
+        }
+    }
+
+    let pages = manpages(config, repo, version)?;
+    for page in &pages {
+        let dest = config.man_dir.join(page.file_name().unwrap_or_default());
+        if dest.exists() {
+            debug!("keeping {}, it is not managed by foundryup", dest.display());
+            continue;
+        }
 
-                    say!("{owner_name}/{repo_name} {version_name}");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(page, &dest)?;
+        #[cfg(not(unix))]
+        fs::copy(page, &dest)?;
+    }
+
+    if !pages.is_empty() && !manpath_includes(config) {
+        let man_root = config.man_dir.parent().unwrap_or(&config.man_dir);
+        say!("hint: add {} to MANPATH to read the manpages with `man`", man_root.display());
+    }
 
-                    for bin in bins {
-                        let bin_path = version_path.join(bin_name(bin));
-                        if bin_path.exists() {
-                            match get_bin_version(&bin_path) {
-                                Ok(v) => say!("- {v}"),
-                                Err(_) => say!("- {bin} (unknown version)"),
-                            }
-                        }
+    Ok(())
+}
+
+/// Returns whether `man` finds the shared man directory, either through `MANPATH` or by deriving
+/// it from the bin directory in `PATH`.
+fn manpath_includes(config: &Config) -> bool {
+    let man_root = config.man_dir.parent().unwrap_or(&config.man_dir);
+    let in_var = |var: &str, dir: &Path| {
+        std::env::var_os(var).is_some_and(|paths| std::env::split_paths(&paths).any(|p| p == dir))
+    };
+
+    match std::env::var_os("MANPATH") {
+        Some(_) => in_var("MANPATH", man_root),
+        None => in_var("PATH", &config.bin_dir),
+    }
+}
+
+pub(crate) fn list(config: &Config) -> Result<()> {
+    let bins = config.network.bins;
+
+    if config.versions_dir.exists() {
+        let versions = installed_versions(config)?;
+        for (repo, version) in &versions {
+            say!("{repo} {version}");
+
+            let version_path = config.version_dir(repo, version);
+            if let Some(metadata) = VersionMetadata::read(&version_path)? {
+                if let Some(commit) = metadata.commit {
+                    say!("- commit {commit}");
+                }
+                if let Some(build) = metadata.build {
+                    say!("- built with {}", build.summary());
+                }
+            }
+            for bin in bins {
+                let bin_path = version_path.join(bin_name(bin));
+                if bin_path.exists() {
+                    match get_bin_version(&bin_path) {
+                        Ok(v) => say!("- {v}"),
+                        Err(_) => say!("- {bin} (unknown version)"),
                     }
-                    eprintln!();
                 }
             }
+            match manpages(config, repo, version)?.len() {
+                0 => say!("- manpages (not installed)"),
+                n => say!("- manpages ({n} pages)"),
+            }
+            eprintln!();
         }
+
+        list_channels(config, &versions)?;
     } else {
         for bin in bins {
             let bin_path = config.bin_path(bin);
@@ -511,18 +949,253 @@ pub(crate) fn list(config: &Config) -> Result<()> {
     Ok(())
 }
 
-pub(crate) fn use_version(config: &Config, repo: &str, version: &str) -> Result<()> {
+/// Prints the version each installed channel points at, as recorded when it was installed, and
+/// whether a newer release was seen when the tags were last fetched.
+fn list_channels(config: &Config, versions: &[(String, String)]) -> Result<()> {
+    let mut repos: Vec<&str> = versions.iter().map(|(repo, _)| repo.as_str()).collect();
+    repos.dedup();
+
+    for repo in repos {
+        let aliases = ChannelAliases::read(&config.versions_dir.join(repo))?;
+        let tags = TagCache::read(&config.cache_dir(repo));
+        for (channel, version) in aliases.iter() {
+            if !config.version_dir(repo, version).exists() {
+                continue;
+            }
+            match releases::newer_release(channel, version, &tags) {
+                Some(newer) => say!("{repo} {channel} → {version} (newer {newer} available)"),
+                None => say!("{repo} {channel} → {version}"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the most recent nightly releases with their publication dates.
+pub(crate) async fn list_nightlies(config: &Config, args: &NightliesArgs) -> Result<()> {
+    let repo = config.network.repo;
+    let downloader = Downloader::new()?;
+    let nightlies =
+        releases::fetch_nightlies(&downloader, repo, args.since.as_deref(), args.limit).await?;
+    TagCache::extend(&config.cache_dir(repo), nightlies.iter().map(|n| n.tag.as_str()))?;
+
+    if nightlies.is_empty() {
+        say!("no nightly releases found for {repo}");
+    }
+    for nightly in nightlies {
+        let installed = config.version_dir(repo, &nightly.tag).exists();
+        say!("{} {}{}", nightly.date(), nightly.tag, if installed { " (installed)" } else { "" });
+    }
+
+    Ok(())
+}
+
+/// Moves versions installed under a channel name (e.g. `versions/<repo>/stable`), before channels
+/// were resolved to release tags, to the release they were built from.
+///
+/// Must be called with the [`FoundryLock`](crate::lock::FoundryLock) held. Binaries are only run
+/// to learn their release when a legacy directory exists.
+pub(crate) fn migrate_channel_dirs(config: &Config) -> Result<()> {
+    let repo = config.network.repo;
+    for channel in releases::CHANNELS {
+        let legacy_dir = config.version_dir(repo, channel);
+        if !legacy_dir.is_dir() {
+            continue;
+        }
+
+        let Some(tag) = config.network.bins.iter().find_map(|bin| {
+            let output = get_bin_version(&legacy_dir.join(bin_name(bin))).ok()?;
+            channel_release_tag(channel, &output)
+        }) else {
+            debug!("could not find the release of {}", legacy_dir.display());
+            continue;
+        };
+
+        let version_dir = config.version_dir(repo, &tag);
+        say!("migrating legacy version '{channel}' of {repo} to {tag}");
+        if version_dir.exists() {
+            fs::remove_dir_all(&legacy_dir)?;
+        } else {
+            fs::rename(&legacy_dir, &version_dir)?;
+        }
+
+        let mut aliases = ChannelAliases::read(&config.versions_dir.join(repo))?;
+        if aliases.get(channel).is_none() {
+            aliases.set(channel, &tag)?;
+        }
+
+        // Relink the binaries if the legacy directory was the active version.
+        let active = ActiveVersion::read(config)?;
+        if active.is_some_and(|active| active.repo == repo && active.version == *channel) {
+            ActiveVersion { repo: repo.to_string(), version: tag.clone() }.write(config)?;
+            #[cfg(unix)]
+            for bin in config.network.bins {
+                let dest = config.bin_path(bin);
+                if std::fs::read_link(&dest).is_ok_and(|target| target.starts_with(&legacy_dir)) {
+                    fs::remove_file(&dest)?;
+                    std::os::unix::fs::symlink(version_dir.join(bin_name(bin)), &dest)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the release tag of a binary installed from a channel, from its `--version` output
+/// (e.g. `v1.5.0` for `Version: 1.5.0-stable`, or `nightly-<sha>` for nightlies).
+fn channel_release_tag(channel: &str, version_output: &str) -> Option<String> {
+    let mut lines = version_output.lines().map(str::trim);
+    if channel == "nightly" {
+        let sha = lines.find_map(|line| line.strip_prefix("Commit SHA: "))?;
+        return Some(format!("nightly-{sha}"));
+    }
+
+    let (_, version) = lines.find_map(|line| line.split_once("Version: "))?;
+    let version = semver::Version::parse(version.trim_end_matches("-stable")).ok()?;
+    Some(format!("v{version}"))
+}
+
+/// Returns all installed versions as `(owner/repo, version)` pairs.
+pub(crate) fn installed_versions(config: &Config) -> Result<Vec<(String, String)>> {
+    fn dir_names(path: &Path) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.path().is_dir() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        Ok(names)
+    }
+
+    let mut versions = Vec::new();
+    if !config.versions_dir.exists() {
+        return Ok(versions);
+    }
+
+    for owner in dir_names(&config.versions_dir)? {
+        for repo in dir_names(&config.versions_dir.join(&owner))? {
+            for version in dir_names(&config.versions_dir.join(&owner).join(&repo))? {
+                versions.push((format!("{owner}/{repo}"), version));
+            }
+        }
+    }
+
+    Ok(versions)
+}
+
+/// Activates an installed version after checking its binaries against the recorded digests.
+///
+/// Channel names (e.g. `stable`) are resolved to the version they were last installed as.
+pub(crate) fn use_version(config: &Config, repo: &str, version: &str, force: bool) -> Result<()> {
+    let aliases = ChannelAliases::read(&config.versions_dir.join(repo))?;
+    let version = match aliases.get(version) {
+        Some(resolved) if config.version_dir(repo, resolved).exists() => {
+            say!("{version} → {resolved}");
+            resolved
+        }
+        _ => version,
+    };
+
+    let installed;
+    let version = match NightlyRequest::parse(version) {
+        Some(NightlyRequest::Commit(sha)) if !config.version_dir(repo, version).exists() => {
+            installed = installed_versions(config)?;
+            let prefix = format!("nightly-{sha}");
+            let Some((_, resolved)) =
+                installed.iter().find(|(r, v)| r == repo && v.starts_with(&prefix))
+            else {
+                bail!("no installed nightly of {repo} matches commit {sha}");
+            };
+            say!("{version} → {resolved}");
+            resolved.as_str()
+        }
+        _ => version,
+    };
+
+    let installed;
+    let version = match releases::parse_requirement(version) {
+        Some(req) if !config.version_dir(repo, version).exists() => {
+            installed = installed_versions(config)?;
+            let candidates = installed.iter().filter(|(r, _)| r == repo).map(|(_, v)| v.as_str());
+            let Some(resolved) = releases::select_version(&req, candidates) else {
+                bail!("no installed version of {repo} matches {version}");
+            };
+            say!("{version} → {resolved}");
+            resolved
+        }
+        _ => version,
+    };
     let version_dir = config.version_dir(repo, version);
 
     if !version_dir.exists() {
         bail!("version {version} not installed for {repo}");
     }
 
+    check_recorded_digests(config, &version_dir, version, force)?;
+    config.ensure_dirs()?;
+    activate(config, repo, version, "use")
+}
+
+/// Reactivates the most recently active version other than the current one.
+pub(crate) fn rollback(config: &Config, force: bool) -> Result<()> {
+    let active = ActiveVersion::read(config)?;
+    let is_active = |repo: &str, version: &str| {
+        active.as_ref().is_some_and(|a| a.repo == repo && a.version == version)
+    };
+
+    // Forks built with `--repo` roll back within the fork.
+    let repo = active.as_ref().map_or(config.network.repo, |active| active.repo.as_str());
+    let history = Activation::history(config)?;
+    let Some(previous) = history.iter().rev().find(|entry| {
+        entry.repo == repo
+            && !is_active(&entry.repo, &entry.version)
+            && config.version_dir(&entry.repo, &entry.version).exists()
+    }) else {
+        bail!("no previously active version of {repo} to roll back to");
+    };
+
+    say!("rolling back to {} (activated {})", previous.version, format_age(previous.timestamp));
+    use_version(config, &previous.repo, &previous.version, force)
+}
+
+/// Formats how long ago a unix timestamp was, e.g. `3 days ago`.
+fn format_age(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let secs = now.saturating_sub(timestamp);
+    let (n, unit) = match secs {
+        0..60 => return "just now".to_string(),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    format!("{n} {unit}{} ago", if n == 1 { "" } else { "s" })
+}
+
+/// Links the binaries of an installed version into the bin directory.
+fn activate(config: &Config, repo: &str, version: &str, source: &str) -> Result<()> {
+    let version_dir = config.version_dir(repo, version);
+    migrate_manpages(config)?;
+
+    ActiveVersion { repo: repo.to_string(), version: version.to_string() }.write(config)?;
+    Activation::record(config, repo, version, source)?;
+
     for bin in config.network.bins {
         let bin_name = bin_name(bin);
         let src = version_dir.join(&bin_name);
         let dest = config.bin_path(bin);
 
+        if !config.is_selected(bin) {
+            if dest.symlink_metadata().is_ok() {
+                fs::remove_file(&dest)?;
+            }
+            continue;
+        }
         if !src.exists() {
             continue;
         }
@@ -558,10 +1231,117 @@ in your 'PATH' to allow the newly installed version to take precedence!
         }
     }
 
+    generate_completions(config, &version_dir)?;
+    link_manpages(config, repo, version)
+}
+
+/// Shells to generate completions of the Foundry binaries for.
+const COMPLETION_SHELLS: &[&str] = &["bash", "zsh", "fish", "powershell"];
+
+/// How long a binary is given to print its completions.
+const COMPLETIONS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Regenerates the completions of the selected binaries of a version with `<bin> completions
+/// <shell>`, into a directory per shell under `completions`.
+///
+/// Only the shells whose profile sources the env scripts, which load the completions, are
+/// generated for.
+fn generate_completions(config: &Config, version_dir: &Path) -> Result<()> {
+    let completions_dir = config.foundry_dir.join("completions");
+    let shells = path::set_up_shells(config)?;
+    let mut generated = false;
+    let mut timed_out = Vec::new();
+
+    for shell in COMPLETION_SHELLS {
+        let shell_dir = completions_dir.join(shell);
+        if shell_dir.exists() {
+            fs::remove_dir_all(&shell_dir)?;
+        }
+        if !shells.contains(shell) {
+            continue;
+        }
+        fs::create_dir_all(&shell_dir)?;
+
+        for bin in &config.components {
+            let path = version_dir.join(bin_name(bin));
+            if !path.exists() || timed_out.contains(bin) {
+                continue;
+            }
+
+            let mut cmd = std::process::Command::new(&path);
+            cmd.args(["completions", shell]);
+            match output_with_timeout(cmd, COMPLETIONS_TIMEOUT) {
+                Ok(Some(output)) if output.status.success() && !output.stdout.is_empty() => {
+                    let file_name = match *shell {
+                        "zsh" => format!("_{bin}"),
+                        "fish" => format!("{bin}.fish"),
+                        "powershell" => format!("{bin}.ps1"),
+                        _ => bin.to_string(),
+                    };
+                    fs::write(shell_dir.join(file_name), output.stdout)?;
+                    generated = true;
+                }
+                Ok(Some(output)) => debug!("{bin} completions {shell} failed: {}", output.status),
+                Ok(None) => {
+                    debug!("{bin} completions {shell} timed out");
+                    timed_out.push(*bin);
+                }
+                Err(e) => debug!("failed to run {bin} completions {shell}: {e}"),
+            }
+        }
+    }
+
+    if generated {
+        say!("generated shell completions in {}", completions_dir.display());
+    }
+
     Ok(())
 }
 
-fn normalize_version(version: &str) -> (String, String) {
+/// Regenerates the completions of the active version, e.g. after more shell profiles were set up.
+pub(crate) fn regenerate_completions(config: &Config) -> Result<()> {
+    let Some(active) = ActiveVersion::read(config)? else { return Ok(()) };
+    let version_dir = config.version_dir(&active.repo, &active.version);
+    if !version_dir.exists() {
+        return Ok(());
+    }
+    generate_completions(config, &version_dir)
+}
+
+/// Runs a command and returns its output, or `None` if it was killed after `timeout`.
+fn output_with_timeout(
+    mut cmd: std::process::Command,
+    timeout: Duration,
+) -> std::io::Result<Option<std::process::Output>> {
+    use std::{io::Read, process::Stdio};
+
+    let mut child =
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+    // Read in the background, the output may not fit in the pipe buffer.
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() >= timeout {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let stdout = reader.join().expect("reader thread panicked")?;
+    Ok(Some(std::process::Output { status, stdout, stderr: Vec::new() }))
+}
+
+pub(crate) fn normalize_version(version: &str) -> (String, String) {
     if version.starts_with("nightly") {
         ("nightly".to_string(), version.to_string())
     } else if version.starts_with(|c: char| c.is_ascii_digit()) {
@@ -572,7 +1352,7 @@ fn normalize_version(version: &str) -> (String, String) {
     }
 }
 
-fn bin_name(name: &str) -> String {
+pub(crate) fn bin_name(name: &str) -> String {
     if cfg!(windows) { format!("{name}.exe") } else { name.to_string() }
 }
 
@@ -582,8 +1362,36 @@ fn get_bin_version(path: &Path) -> Result<String> {
     Ok(version.trim().to_string())
 }
 
-fn rustflags() -> String {
-    std::env::var("RUSTFLAGS").unwrap_or_else(|_| "-C target-cpu=native".to_string())
+/// Returns the `RUSTFLAGS` to build with, optimizing for the host CPU unless `portable` is set.
+fn rustflags(portable: bool) -> String {
+    std::env::var("RUSTFLAGS").unwrap_or_else(|_| {
+        if portable { String::new() } else { "-C target-cpu=native".to_string() }
+    })
+}
+
+/// Returns the output of `rustc -vV` for the toolchain a commit pins, without checking it out.
+async fn pinned_rustc_version(repo: &Path, commit: &str) -> Result<String> {
+    let dir = tempfile::tempdir()?;
+    for file in ["rust-toolchain.toml", "rust-toolchain"] {
+        if let Some(content) = git::show(repo, commit, file).await {
+            fs::write(dir.path().join(file), content)?;
+        }
+    }
+    rustc_version(dir.path()).await
+}
+
+/// Returns the output of `rustc -vV` for the toolchain selected in `dir`.
+async fn rustc_version(dir: &Path) -> Result<String> {
+    let output = tokio::process::Command::new("rustc")
+        .arg("-vV")
+        .current_dir(dir)
+        .output()
+        .await
+        .wrap_err("failed to run rustc")?;
+    if !output.status.success() {
+        bail!("`rustc -vV` failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 #[cfg(test)]
@@ -657,4 +1465,28 @@ mod tests {
         let hashes = parse_attestation_payload(s).unwrap();
         assert!(!hashes.is_empty());
     }
+
+    #[test]
+    fn attestation_links() {
+        let link = "https://github.com/foundry-rs/foundry/attestations/123";
+        assert_eq!(parse_attestation_link(&format!("{link}\n")).unwrap().as_deref(), Some(link));
+        assert_eq!(parse_attestation_link("Not Found").unwrap(), None);
+        assert!(parse_attestation_link("https://github.com.example.com/attestations/123").is_err());
+        assert!(parse_attestation_link("http://github.com/attestations/123").is_err());
+    }
+
+    #[test]
+    fn channel_release_tags() {
+        let stable = "forge Version: 1.5.0-stable\nCommit SHA: 1c57854462289b2e71ee7654cd6666217ed86ffd\nBuild Profile: maxperf";
+        assert_eq!(channel_release_tag("stable", stable).as_deref(), Some("v1.5.0"));
+
+        let nightly = "forge Version: 1.5.0-nightly\nCommit SHA: 1c57854462289b2e71ee7654cd6666217ed86ffd\nBuild Profile: maxperf";
+        assert_eq!(
+            channel_release_tag("nightly", nightly).as_deref(),
+            Some("nightly-1c57854462289b2e71ee7654cd6666217ed86ffd")
+        );
+
+        assert_eq!(channel_release_tag("stable", "forge 0.2.0 (d7bd3f5 2024-01-01)"), None);
+        assert_eq!(channel_release_tag("nightly", ""), None);
+    }
 }
@@ -0,0 +1,82 @@
+use crate::{config::Config, say};
+use eyre::{Result, WrapErr, bail};
+use fs_err as fs;
+use fs4::{TryLockError, fs_err3::FileExt};
+use std::{
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// An advisory lock on the foundry directory.
+///
+/// Held while installing, activating or removing versions so that concurrent foundryup processes
+/// (e.g. parallel CI jobs sharing a cache volume) do not extract into or link the same
+/// directories at the same time. The lock is released when dropped.
+#[derive(Debug)]
+pub(crate) struct FoundryLock {
+    file: fs::File,
+}
+
+impl FoundryLock {
+    /// Acquires the lock, waiting up to `timeout` for another process to release it.
+    pub(crate) async fn acquire(config: &Config, timeout: Duration) -> Result<Self> {
+        fs::create_dir_all(&config.foundry_dir)?;
+        let path = config.foundry_dir.join(".lock");
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let start = Instant::now();
+        let mut waiting = false;
+        loop {
+            // `File::try_lock` is only available from Rust 1.89.
+            match FileExt::try_lock(&file) {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) => {
+                    let holder = holder(&path);
+                    if start.elapsed() >= timeout {
+                        bail!(
+                            "timed out after {}s waiting for {holder} to release {}",
+                            timeout.as_secs(),
+                            path.display()
+                        );
+                    }
+                    if !waiting {
+                        say!("waiting for {holder} to finish...");
+                        waiting = true;
+                    }
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).wrap_err_with(|| format!("failed to lock {}", path.display()));
+                }
+            }
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", std::process::id())?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FoundryLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+    }
+}
+
+/// Describes the process holding the lock, as recorded in the lock file.
+fn holder(path: &Path) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(pid) if !pid.trim().is_empty() => {
+            format!("another foundryup process (PID {})", pid.trim())
+        }
+        _ => "another foundryup process".to_string(),
+    }
+}
@@ -8,22 +8,38 @@
 #[cfg(test)]
 use snapbox as _;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use eyre::Result;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+use tracing::debug;
 
+mod bisect;
+mod build;
 mod cli;
+mod component;
 mod config;
 mod download;
+mod gc;
+mod git;
 mod install;
+mod lock;
+mod metadata;
+mod path;
 mod platform;
 mod process;
+mod releases;
 mod self_update;
+mod state;
+mod uninstall;
+mod verify;
 
-use cli::Cli;
+use cli::{Cli, Commands, SelfCommand, SelfUpdateArgs};
 use config::Config;
+use lock::FoundryLock;
 
 fn main() -> Result<()> {
+    clap_complete::CompleteEnv::with_factory(Cli::command).complete();
+
     color_eyre::install()?;
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -49,33 +65,87 @@ async fn run(cli: Cli) -> Result<()> {
         return Ok(());
     }
 
-    let config = Arc::new(Config::new(cli.network)?);
+    let mut config = Config::new(cli.network)?;
     config.migrate_legacy_versions()?;
+    config.select_components(&cli.components)?;
+    let config = Arc::new(config);
 
     if cli.update {
-        return self_update::run(&config).await;
+        return self_update::run(&config, &SelfUpdateArgs::from_env()?, cli.force).await;
     }
 
-    let update_handle = tokio::spawn({
+    let update_args = if cli.check_updates() {
+        SelfUpdateArgs::from_env().inspect_err(|e| debug!("skipping the update check: {e}")).ok()
+    } else {
+        None
+    };
+    let update_handle = update_args.map(|args| {
         let config = config.clone();
-        async move { self_update::check_for_update(&config).await }
+        let interval = Duration::from_secs(cli.update_check_interval);
+        tokio::spawn(async move { self_update::check_for_update(&config, &args, interval).await })
     });
 
-    if cli.list {
+    if let Some(ref command) = cli.command {
+        match command {
+            Commands::Verify(args) => verify::run(&config, args).await?,
+            Commands::Nightlies(args) => install::list_nightlies(&config, args).await?,
+            Commands::Bisect(args) => {
+                let _lock = lock(&config, &cli).await?;
+                bisect::run(&config, args, cli.force).await?
+            }
+            Commands::Rollback => {
+                let _lock = lock(&config, &cli).await?;
+                install::rollback(&config, cli.force)?
+            }
+            Commands::Gc => {
+                let _lock = lock(&config, &cli).await?;
+                gc::run(&config).await?
+            }
+            Commands::Component(command) => {
+                let _lock = lock(&config, &cli).await?;
+                component::run(&config, command, cli.force).await?
+            }
+            Commands::SetupPath(args) if args.undo => path::undo(&config)?,
+            Commands::SetupPath(_) => path::setup(&config, !cli.no_modify_path)?,
+            Commands::Self_(SelfCommand::Update(args)) => {
+                return self_update::run(&config, args, cli.force).await;
+            }
+            Commands::Self_(SelfCommand::Rollback) => return self_update::rollback(),
+            Commands::Self_(SelfCommand::Uninstall) => {
+                return uninstall::run(&config, cli.yes, cli.lock_timeout()).await;
+            }
+        }
+    } else if cli.list {
         install::list(&config)?;
     } else if let Some(ref version) = cli.use_version {
-        install::use_version(&config, config.network.repo, version)?;
+        let _lock = lock(&config, &cli).await?;
+        install::use_version(&config, config.network.repo, version, cli.force)?;
     } else {
         print_banner();
-        process::check_bins_in_use(&config)?;
+        let _lock = lock(&config, &cli).await?;
+        process::check_bins_in_use(&config, cli.in_use_policy(), cli.yes, cli.lock_timeout())
+            .await?;
         install::run(&config, &cli).await?;
+        if !path::is_set_up(&config) {
+            path::setup(&config, !cli.no_modify_path)?;
+        }
     }
 
-    print_update(update_handle.await?);
+    if let Some(update_handle) = update_handle {
+        print_update(update_handle.await?);
+    }
 
     Ok(())
 }
 
+/// Locks the foundry directory for a command modifying it, then migrates legacy channel
+/// directories.
+async fn lock(config: &Config, cli: &Cli) -> Result<FoundryLock> {
+    let lock = FoundryLock::acquire(config, cli.lock_timeout()).await?;
+    install::migrate_channel_dirs(config)?;
+    Ok(lock)
+}
+
 fn print_banner() {
     eprintln!(
         r#"
@@ -121,6 +191,23 @@ Updating is highly recommended as it gives you access to the latest features and
     }
 }
 
+/// Asks the user to confirm an action, returning `false` if stdin is not a terminal.
+pub(crate) fn confirm(prompt: &str) -> Result<bool> {
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        warn!("cannot ask for confirmation without a terminal, pass --yes to confirm");
+        return Ok(false);
+    }
+
+    eprint!("foundryup: {prompt} [y/N] ");
+    std::io::stderr().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 #[macro_export]
 macro_rules! say {
     ($($arg:tt)*) => {
@@ -0,0 +1,250 @@
+use eyre::{Result, WrapErr};
+use fs_err as fs;
+use serde_json::{Map, Value, json};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Name of the metadata file stored inside each version directory.
+pub(crate) const METADATA_FILE: &str = ".foundryup.json";
+
+/// Metadata recorded alongside an installed version.
+#[derive(Debug, Default)]
+pub(crate) struct VersionMetadata {
+    /// SHA-256 digests of the installed binaries, keyed by binary name.
+    pub digests: HashMap<String, String>,
+    /// Where the digests come from: `attestation` or `local` (computed at install time).
+    pub digest_source: Option<String>,
+    /// The platform the binaries were downloaded for, if installed from a release.
+    pub platform: Option<String>,
+    /// The architecture the binaries were downloaded for, if installed from a release.
+    pub arch: Option<String>,
+    /// The commit the binaries were built from, if built from source.
+    pub commit: Option<String>,
+    /// The settings the binaries were built with, if built from source.
+    pub build: Option<BuildSettings>,
+}
+
+/// The settings a source build was compiled with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BuildSettings {
+    /// The cargo profile.
+    pub profile: String,
+    /// The enabled cargo features, as passed to `--features`.
+    pub features: Option<String>,
+    /// The `RUSTFLAGS` passed to the compiler.
+    pub rustflags: String,
+    /// The output of `rustc -vV`.
+    pub rustc: String,
+}
+
+impl BuildSettings {
+    fn from_json(json: &Value) -> Option<Self> {
+        Some(Self {
+            profile: json["profile"].as_str()?.to_string(),
+            features: json["features"].as_str().map(str::to_string),
+            rustflags: json["rustflags"].as_str()?.to_string(),
+            rustc: json["rustc"].as_str()?.to_string(),
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "profile": self.profile,
+            "features": self.features,
+            "rustflags": self.rustflags,
+            "rustc": self.rustc,
+        })
+    }
+
+    /// Returns a one-line description, e.g.
+    /// `profile release, RUSTFLAGS "-C target-cpu=native", rustc 1.89.0 (29483883e 2025-08-04)`.
+    pub(crate) fn summary(&self) -> String {
+        let mut summary = format!("profile {}", self.profile);
+        if let Some(features) = &self.features {
+            summary.push_str(&format!(", features {features}"));
+        }
+        summary.push_str(&format!(", RUSTFLAGS {:?}", self.rustflags));
+
+        let mut rustc = self.rustc.lines();
+        if let Some(release) = rustc.next() {
+            summary.push_str(&format!(", {release}"));
+        }
+        if let Some(host) = rustc.find_map(|line| line.strip_prefix("host: ")) {
+            summary.push_str(&format!(" on {host}"));
+        }
+        summary
+    }
+}
+
+impl VersionMetadata {
+    /// Reads the metadata of the given version directory, if any was recorded.
+    pub(crate) fn read(version_dir: &Path) -> Result<Option<Self>> {
+        let path = version_dir.join(METADATA_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let json: Value = serde_json::from_str(&content)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+
+        let digests = json["digests"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(bin, digest)| Some((bin.clone(), digest.as_str()?.to_string())))
+            .collect();
+        let digest_source = json["digest_source"].as_str().map(str::to_string);
+        let platform = json["platform"].as_str().map(str::to_string);
+        let arch = json["arch"].as_str().map(str::to_string);
+        let commit = json["commit"].as_str().map(str::to_string);
+        let build = BuildSettings::from_json(&json["build"]);
+
+        Ok(Some(Self { digests, digest_source, platform, arch, commit, build }))
+    }
+
+    /// Writes the metadata into the given version directory.
+    pub(crate) fn write(&self, version_dir: &Path) -> Result<()> {
+        let digests: Map<String, Value> =
+            self.digests.iter().map(|(bin, digest)| (bin.clone(), json!(digest))).collect();
+        let json = json!({
+            "digests": digests,
+            "digest_source": self.digest_source,
+            "platform": self.platform,
+            "arch": self.arch,
+            "commit": self.commit,
+            "build": self.build.as_ref().map(BuildSettings::to_json),
+        });
+
+        fs::write(version_dir.join(METADATA_FILE), serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+}
+
+/// Channel aliases of a repository, mapping e.g. `stable` to the version it was resolved to.
+#[derive(Debug, Default)]
+pub(crate) struct ChannelAliases {
+    path: PathBuf,
+    aliases: BTreeMap<String, String>,
+}
+
+impl ChannelAliases {
+    /// Reads the channel aliases stored in the given repository versions directory.
+    pub(crate) fn read(repo_versions_dir: &Path) -> Result<Self> {
+        let path = repo_versions_dir.join(".channels.json");
+        let mut aliases = BTreeMap::new();
+
+        if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            let json: Value = serde_json::from_str(&content)
+                .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+            for (channel, version) in json.as_object().into_iter().flatten() {
+                if let Some(version) = version.as_str() {
+                    aliases.insert(channel.clone(), version.to_string());
+                }
+            }
+        }
+
+        Ok(Self { path, aliases })
+    }
+
+    /// Returns the version a channel points at.
+    pub(crate) fn get(&self, channel: &str) -> Option<&str> {
+        self.aliases.get(channel).map(String::as_str)
+    }
+
+    /// Points a channel at a version and writes the aliases to disk.
+    pub(crate) fn set(&mut self, channel: &str, version: &str) -> Result<()> {
+        self.aliases.insert(channel.to_string(), version.to_string());
+
+        let json: Map<String, Value> = self
+            .aliases
+            .iter()
+            .map(|(channel, version)| (channel.clone(), json!(version)))
+            .collect();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases.iter().map(|(channel, version)| (channel.as_str(), version.as_str()))
+    }
+}
+
+/// Maximum number of tag names kept in a [`TagCache`].
+const MAX_CACHED_TAGS: usize = 500;
+
+/// Tag names last fetched for a repository, used to complete versions without network access.
+pub(crate) struct TagCache;
+
+impl TagCache {
+    /// Returns the cached tag names, most recently fetched first.
+    pub(crate) fn read(cache_dir: &Path) -> Vec<String> {
+        let Ok(content) = fs::read_to_string(cache_dir.join("tags.json")) else {
+            return Vec::new();
+        };
+        let json: Value = serde_json::from_str(&content).unwrap_or_default();
+        json.as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.as_str().map(str::to_string))
+            .collect()
+    }
+
+    /// Adds freshly fetched tag names in front of the cached ones.
+    pub(crate) fn extend<'a>(
+        cache_dir: &Path,
+        tags: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        let mut names: Vec<String> = tags.into_iter().map(str::to_string).collect();
+        for cached in Self::read(cache_dir) {
+            if !names.contains(&cached) {
+                names.push(cached);
+            }
+        }
+        names.truncate(MAX_CACHED_TAGS);
+
+        fs::create_dir_all(cache_dir)?;
+        fs::write(cache_dir.join("tags.json"), serde_json::to_string_pretty(&names)?)?;
+        Ok(())
+    }
+}
+
+/// The result of the last background check for a foundryup update.
+#[derive(Debug)]
+pub(crate) struct UpdateCheck {
+    /// Unix timestamp of the check, in seconds.
+    pub checked_at: u64,
+    /// The update channel checked.
+    pub channel: String,
+    /// The latest version released on the channel, if any.
+    pub latest: Option<String>,
+}
+
+impl UpdateCheck {
+    pub(crate) fn read(cache_dir: &Path) -> Option<Self> {
+        let content = fs::read_to_string(cache_dir.join("update-check.json")).ok()?;
+        let json: Value = serde_json::from_str(&content).ok()?;
+        Some(Self {
+            checked_at: json["checked_at"].as_u64()?,
+            channel: json["channel"].as_str()?.to_string(),
+            latest: json["latest"].as_str().map(str::to_string),
+        })
+    }
+
+    pub(crate) fn write(&self, cache_dir: &Path) -> Result<()> {
+        let json = json!({
+            "checked_at": self.checked_at,
+            "channel": self.channel,
+            "latest": self.latest,
+        });
+        fs::create_dir_all(cache_dir)?;
+        fs::write(cache_dir.join("update-check.json"), serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{config::Config, install, say};
+use eyre::{Result, eyre};
+use fs_err as fs;
+use std::path::{Path, PathBuf};
+
+/// Comment appended to every line foundryup adds to a shell profile, used to find them on undo.
+const MARKER: &str = "# added by foundryup";
+
+/// A shell startup file that sources one of the env scripts.
+struct Profile {
+    path: PathBuf,
+    /// The line sourcing the env script.
+    line: String,
+    /// Whether the file belongs to foundryup and is removed on undo.
+    owned: bool,
+    /// The shell the env script loads completions for, if any.
+    shell: Option<&'static str>,
+}
+
+/// Writes the env scripts and, if `modify_path` is set, sources them from the shell profiles.
+pub(crate) fn setup(config: &Config, modify_path: bool) -> Result<()> {
+    write_env_scripts(config)?;
+
+    if !modify_path {
+        say!(
+            "to add {} to your PATH, source {}",
+            config.bin_dir.display(),
+            env_script(config, "").display()
+        );
+        return Ok(());
+    }
+
+    let mut modified = false;
+    for profile in profiles(config, false)? {
+        let content = fs::read_to_string(&profile.path).unwrap_or_default();
+        if content.lines().any(|line| line == profile.line) {
+            continue;
+        }
+
+        if let Some(parent) = profile.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let separator = if content.is_empty() || content.ends_with('\n') { "" } else { "\n" };
+        fs::write(&profile.path, format!("{content}{separator}{}\n", profile.line))?;
+        say!("added {} to PATH in {}", config.bin_dir.display(), profile.path.display());
+        modified = true;
+    }
+
+    if modified {
+        // The completions are only generated for the shells set up when the version was activated.
+        install::regenerate_completions(config)?;
+        say!(
+            "restart your shell, or run `. \"{}\"` to use it now",
+            env_script(config, "").display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Removes the lines added to the shell profiles and the env scripts.
+pub(crate) fn undo(config: &Config) -> Result<()> {
+    for profile in profiles(config, true)? {
+        if !profile.path.exists() {
+            continue;
+        }
+
+        if profile.owned {
+            fs::remove_file(&profile.path)?;
+        } else {
+            let content = fs::read_to_string(&profile.path)?;
+            if !content.lines().any(|line| line.ends_with(MARKER)) {
+                continue;
+            }
+            let kept: String = content
+                .lines()
+                .filter(|line| !line.ends_with(MARKER))
+                .map(|line| format!("{line}\n"))
+                .collect();
+            fs::write(&profile.path, kept)?;
+        }
+        say!("removed {} from PATH in {}", config.bin_dir.display(), profile.path.display());
+    }
+
+    for ext in ["", ".fish", ".nu", ".ps1"] {
+        let script = env_script(config, ext);
+        if script.exists() {
+            fs::remove_file(script)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the shells whose profile sources an env script, to generate completions for.
+pub(crate) fn set_up_shells(config: &Config) -> Result<Vec<&'static str>> {
+    let mut shells = Vec::new();
+    for profile in profiles(config, true)? {
+        let Some(shell) = profile.shell else { continue };
+        let content = fs::read_to_string(&profile.path).unwrap_or_default();
+        if content.lines().any(|line| line == profile.line) && !shells.contains(&shell) {
+            shells.push(shell);
+        }
+    }
+    Ok(shells)
+}
+
+/// Returns whether the env scripts were written, i.e. the PATH was set up before.
+pub(crate) fn is_set_up(config: &Config) -> bool {
+    env_script(config, "").exists()
+}
+
+fn env_script(config: &Config, ext: &str) -> PathBuf {
+    config.foundry_dir.join(format!("env{ext}"))
+}
+
+/// Writes scripts prepending the bin directory to PATH, once, for each supported shell.
+///
+/// The scripts also load the completions generated on activation from `completions/<shell>`.
+fn write_env_scripts(config: &Config) -> Result<()> {
+    let bin_dir = config.bin_dir.to_str().ok_or_else(|| eyre!("bin directory is not UTF-8"))?;
+    let completions_dir = config.foundry_dir.join("completions");
+    let completions_dir =
+        completions_dir.to_str().ok_or_else(|| eyre!("completions directory is not UTF-8"))?;
+    fs::create_dir_all(&config.foundry_dir)?;
+
+    fs::write(
+        env_script(config, ""),
+        format!(
+            r#"#!/bin/sh
+# foundryup shell setup
+case ":${{PATH}}:" in
+    *:"{bin_dir}":*)
+        ;;
+    *)
+        export PATH="{bin_dir}:$PATH"
+        ;;
+esac
+if [ -n "${{BASH_VERSION:-}}" ]; then
+    for completion in "{completions_dir}/bash"/*; do
+        [ -f "$completion" ] && . "$completion"
+    done
+elif [ -n "${{ZSH_VERSION:-}}" ]; then
+    FPATH="{completions_dir}/zsh${{FPATH:+:$FPATH}}"
+fi
+"#
+        ),
+    )?;
+    fs::write(
+        env_script(config, ".fish"),
+        format!(
+            r#"# foundryup shell setup
+if not contains "{bin_dir}" $PATH
+    set -gx PATH "{bin_dir}" $PATH
+end
+if not contains "{completions_dir}/fish" $fish_complete_path
+    set -g fish_complete_path "{completions_dir}/fish" $fish_complete_path
+end
+"#
+        ),
+    )?;
+    fs::write(
+        env_script(config, ".nu"),
+        format!(
+            r#"# foundryup shell setup
+$env.PATH = ($env.PATH | split row (char esep) | prepend '{bin_dir}' | uniq)
+"#
+        ),
+    )?;
+    fs::write(
+        env_script(config, ".ps1"),
+        format!(
+            r#"# foundryup shell setup
+if (-not (($env:Path -split [IO.Path]::PathSeparator) -contains '{bin_dir}')) {{
+    $env:Path = '{bin_dir}' + [IO.Path]::PathSeparator + $env:Path
+}}
+Get-ChildItem '{completions_dir}/powershell' -Filter *.ps1 -ErrorAction SilentlyContinue |
+    ForEach-Object {{ . $_.FullName }}
+"#
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Returns the shell profiles to source the env scripts from.
+///
+/// With `all`, returns every profile foundryup may have modified, detected or not.
+fn profiles(config: &Config, all: bool) -> Result<Vec<Profile>> {
+    let home = home::home_dir().ok_or_else(|| eyre!("could not determine home directory"))?;
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".config"));
+    let has = |cmd: &str| which::which(cmd).is_ok();
+    let source = |command: &str, ext: &str| {
+        format!("{command} \"{}\" {MARKER}", env_script(config, ext).display())
+    };
+
+    let mut profiles = Vec::new();
+    let mut add = |path: PathBuf, line: String, owned: bool, shell, detected: bool| {
+        if all || detected {
+            profiles.push(Profile { path, line, owned, shell });
+        }
+    };
+
+    if cfg!(unix) {
+        add(home.join(".profile"), source(".", ""), false, None, true);
+        for bashrc in [".bashrc", ".bash_profile"] {
+            let path = home.join(bashrc);
+            let exists = path.exists();
+            add(path, source(".", ""), false, Some("bash"), exists);
+        }
+
+        let zdotdir =
+            std::env::var_os("ZDOTDIR").map(PathBuf::from).unwrap_or_else(|| home.clone());
+        let zshenv = zdotdir.join(".zshenv");
+        let detected = has("zsh") || zshenv.exists() || zdotdir.join(".zshrc").exists();
+        add(zshenv, source(".", ""), false, Some("zsh"), detected);
+    }
+
+    let fish_dir = config_home.join("fish");
+    let detected = has("fish") || fish_dir.exists();
+    let fish_conf = fish_dir.join("conf.d/foundryup.fish");
+    add(fish_conf, source("source", ".fish"), true, Some("fish"), detected);
+
+    let nu_config = nushell_config_dir(&home, &config_home).join("config.nu");
+    let exists = nu_config.exists();
+    add(
+        nu_config,
+        format!("source '{}' {MARKER}", env_script(config, ".nu").display()),
+        false,
+        None,
+        exists,
+    );
+
+    let ps_profile = powershell_profile(&home, &config_home);
+    let detected = ps_profile.exists() || has("pwsh");
+    add(ps_profile, source(".", ".ps1"), false, Some("powershell"), detected);
+
+    Ok(profiles)
+}
+
+fn nushell_config_dir(home: &Path, config_home: &Path) -> PathBuf {
+    if cfg!(target_os = "macos") && std::env::var_os("XDG_CONFIG_HOME").is_none() {
+        home.join("Library/Application Support/nushell")
+    } else if cfg!(windows) {
+        std::env::var_os("APPDATA")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join("AppData/Roaming"))
+            .join("nushell")
+    } else {
+        config_home.join("nushell")
+    }
+}
+
+fn powershell_profile(home: &Path, config_home: &Path) -> PathBuf {
+    let dir = if cfg!(windows) {
+        home.join("Documents/PowerShell")
+    } else {
+        config_home.join("powershell")
+    };
+    dir.join("Microsoft.PowerShell_profile.ps1")
+}
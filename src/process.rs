@@ -1,23 +1,134 @@
-use crate::{config::Config, warn};
-use eyre::Result;
-use sysinfo::System;
+use crate::{config::Config, confirm, say, warn};
+use eyre::{Result, bail};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use sysinfo::{Pid, ProcessStatus, ProcessesToUpdate, Signal, System};
 
-pub(crate) fn check_bins_in_use(config: &Config) -> Result<()> {
-    let bins = config.network.bins;
+/// How long terminated processes are given to exit before they are killed.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// What to do when Foundry binaries managed by foundryup are running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InUsePolicy {
+    /// Abort the installation.
+    Fail,
+    /// Wait for the processes to exit.
+    Wait,
+    /// Terminate the processes after confirmation.
+    Kill,
+}
+
+/// Checks for running Foundry binaries, waiting up to `timeout` for them to exit with
+/// [`InUsePolicy::Wait`].
+pub(crate) async fn check_bins_in_use(
+    config: &Config,
+    policy: InUsePolicy,
+    yes: bool,
+    timeout: Duration,
+) -> Result<()> {
     let mut sys = System::new();
-    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
-
-    for (pid, proc) in sys.processes() {
-        if let Some(exe) = proc.exe().or_else(|| proc.cmd().first().map(AsRef::as_ref))
-            && let Some(exe_fname) = exe.file_name()
-            && let Some(exe_fname) = exe_fname.to_str()
-            && let Some(bin) = bins.iter().find(|&&bin| exe_fname.starts_with(bin))
-        {
-            warn!(
-                "'{bin}' is currently running (PID: {pid}), please stop the process and try again"
-            );
+    let running = running_bins(config, &mut sys);
+    if running.is_empty() {
+        return Ok(());
+    }
+
+    for (pid, bin) in &running {
+        warn!("'{bin}' is currently running (PID: {pid})");
+    }
+
+    let pids: Vec<Pid> = running.iter().map(|(pid, _)| *pid).collect();
+    match policy {
+        InUsePolicy::Fail => {
+            bail!("stop the running processes and try again, or pass --wait or --kill")
+        }
+        InUsePolicy::Wait => {
+            say!("waiting for the running processes to exit...");
+            if !wait_for_exit(&mut sys, &pids, timeout).await {
+                bail!(
+                    "timed out after {}s waiting for the running processes to exit",
+                    timeout.as_secs()
+                );
+            }
+        }
+        InUsePolicy::Kill => {
+            if !yes && !confirm("terminate the running processes?")? {
+                bail!("aborted, the running processes were not terminated");
+            }
+
+            // Give the processes a chance to clean up before killing them.
+            for (pid, bin) in &running {
+                if let Some(process) = sys.process(*pid)
+                    && !process.kill_with(Signal::Term).unwrap_or_else(|| process.kill())
+                {
+                    bail!("failed to terminate '{bin}' (PID: {pid})");
+                }
+            }
+            if !wait_for_exit(&mut sys, &pids, KILL_GRACE_PERIOD).await {
+                for (pid, bin) in &running {
+                    if let Some(process) = sys.process(*pid) {
+                        warn!("'{bin}' (PID: {pid}) did not exit, killing it");
+                        process.kill();
+                    }
+                }
+                wait_for_exit(&mut sys, &pids, KILL_GRACE_PERIOD).await;
+            }
         }
     }
 
     Ok(())
 }
+
+/// Waits up to `timeout` for the processes to exit, returning whether they all did.
+async fn wait_for_exit(sys: &mut System, pids: &[Pid], timeout: Duration) -> bool {
+    let start = std::time::Instant::now();
+    loop {
+        sys.refresh_processes(ProcessesToUpdate::Some(pids), true);
+        let exited =
+            |pid| sys.process(pid).is_none_or(|process| process.status() == ProcessStatus::Zombie);
+        if pids.iter().copied().all(exited) {
+            return true;
+        }
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Returns the processes running a binary from the bin or versions directory.
+fn running_bins(config: &Config, sys: &mut System) -> Vec<(Pid, &'static str)> {
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+
+    let managed_dirs: Vec<PathBuf> = [&config.bin_dir, &config.versions_dir]
+        .into_iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .collect();
+    if managed_dirs.is_empty() {
+        return Vec::new();
+    }
+
+    let mut running: Vec<_> = sys
+        .processes()
+        .iter()
+        .filter_map(|(pid, proc)| {
+            let exe = proc.exe().or_else(|| proc.cmd().first().map(AsRef::as_ref))?;
+            let bin = managed_bin(config, &managed_dirs, exe)?;
+            Some((*pid, bin))
+        })
+        .collect();
+    running.sort();
+    running
+}
+
+/// Returns the name of the Foundry binary `exe` resolves to, if it lives in a managed directory.
+fn managed_bin(config: &Config, managed_dirs: &[PathBuf], exe: &Path) -> Option<&'static str> {
+    let exe = exe.canonicalize().ok()?;
+    if !managed_dirs.iter().any(|dir| exe.starts_with(dir)) {
+        return None;
+    }
+
+    let name = exe.file_stem()?.to_str()?;
+    config.network.bins.iter().copied().find(|&bin| bin == name)
+}
@@ -0,0 +1,409 @@
+use crate::download::Downloader;
+use eyre::{Result, WrapErr, bail};
+use semver::{Version, VersionReq};
+use serde_json::Value;
+use tracing::debug;
+
+/// Moving release tags that point at a concrete release.
+pub(crate) const CHANNELS: &[&str] = &["stable", "nightly"];
+
+/// Number of items requested per page from the GitHub API.
+const PER_PAGE: usize = 100;
+
+/// Maximum number of pages of tags to look through.
+const MAX_TAG_PAGES: usize = 10;
+
+/// Maximum number of pages of releases to look through.
+const MAX_RELEASE_PAGES: usize = 10;
+
+pub(crate) fn is_channel(version: &str) -> bool {
+    CHANNELS.contains(&version)
+}
+
+/// Resolves a channel (e.g. `stable`) to the immutable release tag it currently points at
+/// (e.g. `v1.5.0`, or `nightly-<sha>` for nightlies).
+pub(crate) async fn resolve_channel(
+    downloader: &Downloader,
+    repo: &str,
+    channel: &str,
+) -> Result<String> {
+    let commit = fetch_commit(downloader, repo, channel).await?;
+    debug!("{channel} points at commit {commit}");
+
+    // Nightlies are tagged after their commit, only check that the tag exists.
+    if channel == "nightly" {
+        let nightly_tag = format!("nightly-{commit}");
+        fetch_commit(downloader, repo, &nightly_tag).await?;
+        return Ok(nightly_tag);
+    }
+
+    let mut semver_tag = None;
+
+    for page in 1..=MAX_TAG_PAGES {
+        let tags = fetch_tag_page(downloader, repo, page).await?;
+
+        for tag in &tags {
+            if tag.commit != commit || is_channel(&tag.name) {
+                continue;
+            }
+
+            if parse_tag_version(&tag.name).is_some() {
+                semver_tag.get_or_insert_with(|| tag.name.clone());
+            }
+        }
+
+        if let Some(tag) = semver_tag {
+            return Ok(tag);
+        }
+        if tags.len() < PER_PAGE {
+            break;
+        }
+    }
+
+    bail!("no release tag found for {channel} (commit {commit})")
+}
+
+/// Parses a version requirement such as `1.5`, `^1.4`, `>=1.3,<2` or `latest`.
+///
+/// Returns `None` for exact versions, channels and other tags, which are used as is. Partial
+/// versions match the latest release with that prefix, e.g. `1.5` matches `1.5.x`.
+pub(crate) fn parse_requirement(version: &str) -> Option<VersionReq> {
+    if version == "latest" {
+        return Some(VersionReq::STAR);
+    }
+    if is_channel(version) || version.starts_with("nightly") || parse_tag_version(version).is_some()
+    {
+        return None;
+    }
+
+    let partial = version.strip_prefix('v').unwrap_or(version);
+    if !partial.is_empty() && partial.split('.').all(|n| n.parse::<u64>().is_ok()) {
+        return VersionReq::parse(&format!("~{partial}")).ok();
+    }
+
+    VersionReq::parse(version).ok()
+}
+
+/// Returns the highest version tag matching the requirement.
+///
+/// Resolving against release tags rather than git tags skips tags without a published release.
+pub(crate) fn select_version<'a>(
+    req: &VersionReq,
+    tags: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    tags.into_iter()
+        .filter_map(|tag| Some((parse_tag_version(tag)?, tag)))
+        .filter(|(version, _)| req.matches(version))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag)
+}
+
+/// Returns the newest release of a channel among the cached tags, if it is newer than `version`.
+///
+/// Stable releases are compared by version. Nightlies are only known to be newer when they were
+/// fetched before the installed one, as the tags are cached most recently fetched first.
+pub(crate) fn newer_release<'a>(
+    channel: &str,
+    version: &str,
+    tags: &'a [String],
+) -> Option<&'a str> {
+    if channel == "nightly" {
+        let latest = tags.iter().position(|tag| tag.starts_with("nightly-"))?;
+        let installed = tags.iter().position(|tag| tag == version)?;
+        return (latest < installed).then(|| tags[latest].as_str());
+    }
+
+    let installed = parse_tag_version(version)?;
+    tags.iter()
+        .filter_map(|tag| Some((parse_tag_version(tag)?, tag.as_str())))
+        .filter(|(version, _)| version.pre.is_empty() && *version > installed)
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag)
+}
+
+/// Parses a release tag such as `v1.5.0` as a semantic version.
+fn parse_tag_version(tag: &str) -> Option<Version> {
+    Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+/// A git tag of a remote repository.
+#[derive(Debug, Clone)]
+pub(crate) struct Tag {
+    pub name: String,
+    pub commit: String,
+}
+
+async fn fetch_tag_page(downloader: &Downloader, repo: &str, page: usize) -> Result<Vec<Tag>> {
+    let url = format!("https://api.github.com/repos/{repo}/tags?per_page={PER_PAGE}&page={page}");
+    let json = fetch_json(downloader, &url).await?;
+
+    Ok(json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tag| {
+            Some(Tag {
+                name: tag["name"].as_str()?.to_string(),
+                commit: tag["commit"]["sha"].as_str()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// A nightly release requested by publication date or commit instead of by its full tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NightlyRequest<'a> {
+    /// `nightly-YYYY-MM-DD`: the last nightly published on that day (UTC).
+    Date(&'a str),
+    /// `nightly@<sha>`: the nightly built from the commit with that (short) SHA.
+    Commit(&'a str),
+}
+
+impl<'a> NightlyRequest<'a> {
+    pub(crate) fn parse(version: &'a str) -> Option<Self> {
+        if let Some(sha) = version.strip_prefix("nightly@") {
+            let is_sha = sha.len() >= 4 && sha.chars().all(|c| c.is_ascii_hexdigit());
+            return is_sha.then_some(Self::Commit(sha));
+        }
+
+        let date = version.strip_prefix("nightly-")?;
+        let is_date = date.len() == 10
+            && date
+                .char_indices()
+                .all(|(i, c)| if i == 4 || i == 7 { c == '-' } else { c.is_ascii_digit() });
+        is_date.then_some(Self::Date(date))
+    }
+
+    /// Returns whether the given nightly release satisfies this request.
+    pub(crate) fn matches(&self, release: &Release) -> bool {
+        match *self {
+            Self::Date(date) => release.is_nightly() && release.date() == date,
+            Self::Commit(sha) => release.tag.starts_with(&format!("nightly-{sha}")),
+        }
+    }
+}
+
+/// Resolves a nightly requested by date or commit to its release tag.
+pub(crate) async fn resolve_nightly(
+    downloader: &Downloader,
+    repo: &str,
+    request: NightlyRequest<'_>,
+) -> Result<String> {
+    for page in 1..=MAX_RELEASE_PAGES {
+        let releases = fetch_release_page(downloader, repo, page).await?;
+
+        for release in releases.iter().filter(|r| r.is_nightly()) {
+            if request.matches(release) {
+                return Ok(release.tag.clone());
+            }
+            // Releases are sorted from newest to oldest.
+            if let NightlyRequest::Date(date) = request
+                && release.date() < date
+            {
+                bail!("no nightly release published on {date}");
+            }
+        }
+
+        if releases.len() < PER_PAGE {
+            break;
+        }
+    }
+
+    match request {
+        NightlyRequest::Date(date) => bail!("no nightly release published on {date}"),
+        NightlyRequest::Commit(sha) => bail!("no nightly release found for commit {sha}"),
+    }
+}
+
+/// A GitHub release of a remote repository.
+#[derive(Debug, Clone)]
+pub(crate) struct Release {
+    pub tag: String,
+    /// Publication time in RFC 3339 format, e.g. `2026-10-01T03:04:05Z`.
+    pub published_at: String,
+    /// Names of the uploaded assets.
+    pub assets: Vec<String>,
+}
+
+impl Release {
+    /// Returns the publication date (`YYYY-MM-DD`).
+    pub(crate) fn date(&self) -> &str {
+        self.published_at.get(..10).unwrap_or(&self.published_at)
+    }
+
+    /// Returns whether this is a per-commit nightly release (`nightly-<sha>`).
+    pub(crate) fn is_nightly(&self) -> bool {
+        self.tag.starts_with("nightly-")
+    }
+}
+
+/// Fetches the nightly releases of a repository published on or after `since` (`YYYY-MM-DD`),
+/// newest first.
+pub(crate) async fn fetch_nightlies(
+    downloader: &Downloader,
+    repo: &str,
+    since: Option<&str>,
+    limit: usize,
+) -> Result<Vec<Release>> {
+    let mut nightlies = Vec::new();
+
+    for page in 1..=MAX_RELEASE_PAGES {
+        let releases = fetch_release_page(downloader, repo, page).await?;
+        let last = releases.len() < PER_PAGE;
+
+        for release in releases.into_iter().filter(Release::is_nightly) {
+            if since.is_some_and(|since| release.date() < since) || nightlies.len() >= limit {
+                return Ok(nightlies);
+            }
+            nightlies.push(release);
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    Ok(nightlies)
+}
+
+/// Fetches the releases of a repository, newest first, until `done` returns true for the releases
+/// fetched so far.
+pub(crate) async fn fetch_releases(
+    downloader: &Downloader,
+    repo: &str,
+    mut done: impl FnMut(&[Release]) -> bool,
+) -> Result<Vec<Release>> {
+    let mut releases = Vec::new();
+
+    for page in 1..=MAX_RELEASE_PAGES {
+        let page = fetch_release_page(downloader, repo, page).await?;
+        let last = page.len() < PER_PAGE;
+        releases.extend(page);
+
+        if last || done(&releases) {
+            break;
+        }
+    }
+
+    Ok(releases)
+}
+
+async fn fetch_release_page(
+    downloader: &Downloader,
+    repo: &str,
+    page: usize,
+) -> Result<Vec<Release>> {
+    let url =
+        format!("https://api.github.com/repos/{repo}/releases?per_page={PER_PAGE}&page={page}");
+    let json = fetch_json(downloader, &url).await?;
+
+    Ok(json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|release| release["draft"] != true)
+        .filter_map(|release| {
+            Some(Release {
+                tag: release["tag_name"].as_str()?.to_string(),
+                published_at: release["published_at"].as_str()?.to_string(),
+                assets: release["assets"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|asset| asset["name"].as_str().map(str::to_string))
+                    .collect(),
+            })
+        })
+        .collect())
+}
+
+/// Returns the commit SHA a tag points at, peeling annotated tags.
+async fn fetch_commit(downloader: &Downloader, repo: &str, tag: &str) -> Result<String> {
+    let url = format!("https://api.github.com/repos/{repo}/git/ref/tags/{tag}");
+    let mut object = fetch_json(downloader, &url).await?["object"].take();
+
+    while object["type"] == "tag" {
+        let Some(url) = object["url"].as_str() else { break };
+        object = fetch_json(downloader, url).await?["object"].take();
+    }
+
+    object["sha"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| eyre::eyre!("missing commit for tag {tag}"))
+}
+
+async fn fetch_json(downloader: &Downloader, url: &str) -> Result<Value> {
+    let response = downloader.download_to_string(url).await?;
+    serde_json::from_str(&response).wrap_err_with(|| format!("failed to parse JSON from {url}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAGS: &[&str] =
+        &["stable", "nightly", "nightly-abc", "v1.3.0", "v1.4.2", "v1.5.0", "v1.5.1", "v2.0.0-rc1"];
+
+    fn select(req: &str) -> Option<&'static str> {
+        select_version(&parse_requirement(req)?, TAGS.iter().copied())
+    }
+
+    #[test]
+    fn exact_versions_and_tags_are_not_requirements() {
+        for version in ["1.5.0", "v1.5.0", "stable", "nightly", "nightly-abc"] {
+            assert!(parse_requirement(version).is_none(), "{version}");
+        }
+    }
+
+    #[test]
+    fn parse_nightly_requests() {
+        assert_eq!(
+            NightlyRequest::parse("nightly-2026-10-01"),
+            Some(NightlyRequest::Date("2026-10-01"))
+        );
+        assert_eq!(
+            NightlyRequest::parse("nightly@1c57854"),
+            Some(NightlyRequest::Commit("1c57854"))
+        );
+        assert_eq!(NightlyRequest::parse("nightly"), None);
+        assert_eq!(NightlyRequest::parse("nightly-1c57854462289b2e71ee7654cd6666217ed86ffd"), None);
+        assert_eq!(NightlyRequest::parse("nightly@main"), None);
+    }
+
+    #[test]
+    fn match_nightly_requests() {
+        let release = Release {
+            tag: "nightly-1c57854462289b2e71ee7654cd6666217ed86ffd".to_string(),
+            published_at: "2026-10-01T03:04:05Z".to_string(),
+            assets: Vec::new(),
+        };
+        assert!(NightlyRequest::Date("2026-10-01").matches(&release));
+        assert!(!NightlyRequest::Date("2026-10-02").matches(&release));
+        assert!(NightlyRequest::Commit("1c5785").matches(&release));
+        assert!(!NightlyRequest::Commit("2c5785").matches(&release));
+    }
+
+    #[test]
+    fn resolve_requirements() {
+        assert_eq!(select("latest"), Some("v1.5.1"));
+        assert_eq!(select("1"), Some("v1.5.1"));
+        assert_eq!(select("1.4"), Some("v1.4.2"));
+        assert_eq!(select("v1.3"), Some("v1.3.0"));
+        assert_eq!(select("^1.4"), Some("v1.5.1"));
+        assert_eq!(select(">=1.3,<1.5"), Some("v1.4.2"));
+        assert_eq!(select(">=2.0.0-rc1"), Some("v2.0.0-rc1"));
+        assert_eq!(select("3"), None);
+    }
+
+    #[test]
+    fn newer_releases() {
+        let tags: Vec<String> =
+            ["nightly-b", "v1.5.0", "nightly-a", "v1.6.0-rc1", "v1.4.0"].map(str::to_string).into();
+        assert_eq!(newer_release("stable", "v1.4.0", &tags), Some("v1.5.0"));
+        assert_eq!(newer_release("stable", "v1.5.0", &tags), None);
+        assert_eq!(newer_release("nightly", "nightly-a", &tags), Some("nightly-b"));
+        assert_eq!(newer_release("nightly", "nightly-b", &tags), None);
+        assert_eq!(newer_release("nightly", "nightly-c", &tags), None);
+    }
+}
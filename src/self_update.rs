@@ -1,26 +1,46 @@
 use crate::{
+    cli::{SelfUpdateArgs, UpdateChannel},
     config::{Config, FOUNDRYUP_REPO, VERSION},
-    download::Downloader,
-    platform::Target,
-    say,
+    download::{Downloader, compute_sha256},
+    install,
+    metadata::UpdateCheck,
+    platform::{Platform, Target},
+    releases, say, warn,
 };
-use eyre::{Result, WrapErr};
+use eyre::{Result, WrapErr, bail};
 use fs_err as fs;
 use semver::Version;
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tracing::debug;
 
-pub(crate) async fn run(config: &Config) -> Result<()> {
-    say!("checking for updates...");
-
-    let new_version = match check_for_update(config).await {
-        Ok(Some(v)) => v,
-        Ok(None) => {
-            say!("foundryup is already up to date (installed: {VERSION})");
+pub(crate) async fn run(config: &Config, args: &SelfUpdateArgs, force: bool) -> Result<()> {
+    let new_version = if let Some(to) = &args.to {
+        let version = to.trim_start_matches('v');
+        Version::parse(version)
+            .wrap_err_with(|| format!("invalid foundryup version '{to}', expected e.g. 0.0.3"))?;
+        if version == VERSION {
+            say!("foundryup v{VERSION} is already installed");
             return Ok(());
         }
-        Err(e) => {
-            debug!("update check failed: {e}");
-            return Err(e).wrap_err("failed to check for updates");
+        check_released(version).await?;
+        version.to_string()
+    } else {
+        say!("checking for updates...");
+        match latest_version(args.channel).await.and_then(newer_than_installed) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                say!("foundryup is already up to date (installed: {VERSION})");
+                return Ok(());
+            }
+            Err(e) => {
+                debug!("update check failed: {e}");
+                return Err(e).wrap_err("failed to check for updates");
+            }
         }
     };
 
@@ -28,68 +48,356 @@ pub(crate) async fn run(config: &Config) -> Result<()> {
 
     let downloader = Downloader::new()?;
     let target = Target::detect(None, None)?;
-    let archive_name = format!(
+    let asset_name = format!(
         "foundryup_{platform}_{arch}",
         platform = target.platform.as_str(),
         arch = target.arch.as_str()
     );
+    let binary_name = if target.platform == Platform::Win32 {
+        format!("{asset_name}.exe")
+    } else {
+        asset_name.clone()
+    };
 
-    let download_url = format!(
-        "https://github.com/{FOUNDRYUP_REPO}/releases/download/v{new_version}/{archive_name}"
-    );
+    let release_url =
+        format!("https://github.com/{FOUNDRYUP_REPO}/releases/download/v{new_version}/");
+
+    let expected_hash = if force {
+        say!("skipped the attestation digest check due to --force flag");
+        None
+    } else {
+        let attestation_url = format!("{release_url}{asset_name}.attestation.txt");
+        let expected_hash = fetch_expected_hash(&downloader, &attestation_url, &binary_name)
+            .await
+            .wrap_err_with(|| {
+                format!(
+                    "failed to check foundryup v{new_version} against its attestation, pass --force to \
+                     skip (INSECURE)"
+                )
+            })?;
+        Some(expected_hash)
+    };
 
     let temp_dir = tempfile::tempdir()?;
     let temp_path = temp_dir.path().join("foundryup_new");
 
     downloader
-        .download_to_file(&download_url, &temp_path)
+        .download_to_file(&format!("{release_url}{binary_name}"), &temp_path)
         .await
         .wrap_err_with(|| format!("failed to download foundryup v{new_version}"))?;
 
+    if let Some(expected_hash) = expected_hash {
+        let actual_hash = compute_sha256(&temp_path)?;
+        if actual_hash != expected_hash {
+            bail!(
+                "foundryup v{new_version} does not match its attestation:\n  expected: {expected_hash}\n  actual:   {actual_hash}"
+            );
+        }
+        say!("foundryup v{new_version} matches the digest of its attestation ✓");
+    }
+
     say!("installing update...");
 
+    let exe = current_exe()?;
+    let backup = backup_path(&exe);
+    fs::copy(&exe, &backup).wrap_err("failed to back up the foundryup binary")?;
+
     self_replace::self_replace(&temp_path).wrap_err("failed to replace foundryup binary")?;
 
     let _ = fs::remove_file(temp_path);
 
     let _ = config;
 
+    if let Err(e) = smoke_test(&exe, &new_version) {
+        warn!("foundryup v{new_version} failed to start: {e}");
+        self_replace::self_replace(&backup).wrap_err("failed to restore the previous foundryup")?;
+        bail!("restored foundryup {VERSION}, the update was not installed");
+    }
+
     say!("successfully updated foundryup: {VERSION} → {new_version}");
+    say!("the previous version can be restored with `foundryup self rollback`");
 
     Ok(())
 }
 
-pub(crate) async fn check_for_update(_config: &Config) -> Result<Option<String>> {
-    let downloader = Downloader::new()?;
+/// Restores the foundryup binary replaced by the last update, keeping the current one as the new
+/// backup so that the rollback can be undone.
+pub(crate) fn rollback() -> Result<()> {
+    let exe = current_exe()?;
+    let backup = backup_path(&exe);
+    if !backup.exists() {
+        bail!("no previous foundryup binary found at {}", backup.display());
+    }
 
-    let releases_url = format!("https://api.github.com/repos/{FOUNDRYUP_REPO}/releases/latest");
+    let previous = binary_version(&backup)
+        .wrap_err_with(|| format!("the binary at {} does not start", backup.display()))?;
 
-    debug!("fetching latest release from {releases_url}");
+    let temp_dir = tempfile::tempdir()?;
+    let restored = temp_dir.path().join("foundryup_old");
+    fs::copy(&backup, &restored)?;
+    fs::copy(&exe, &backup).wrap_err("failed to back up the foundryup binary")?;
+    self_replace::self_replace(&restored).wrap_err("failed to replace foundryup binary")?;
 
-    let response = downloader
-        .download_to_string(&releases_url)
-        .await
-        .wrap_err("failed to fetch release information")?;
+    say!("rolled back foundryup: {VERSION} → {previous}");
+    Ok(())
+}
 
-    let json: serde_json::Value =
-        serde_json::from_str(&response).wrap_err("failed to parse release JSON")?;
+/// Returns the path of the running foundryup binary, resolving symlinks like `self_replace` does.
+fn current_exe() -> Result<PathBuf> {
+    let exe = std::env::current_exe().wrap_err("failed to locate the foundryup binary")?;
+    Ok(fs::canonicalize(exe)?)
+}
 
-    let tag_name = json["tag_name"]
-        .as_str()
-        .ok_or_else(|| eyre::eyre!("missing tag_name in release response"))?;
+/// Returns where the binary replaced by an update is kept, `foundryup.old` next to `exe`.
+pub(crate) fn backup_path(exe: &Path) -> PathBuf {
+    exe.with_extension("old")
+}
+
+/// Runs `foundryup --version` with the given binary, returning the version it reports.
+/// Checks that the binary starts and reports the expected version, so that a wrong asset is not
+/// kept either.
+fn smoke_test(exe: &Path, expected: &str) -> Result<()> {
+    let version = binary_version(exe)?;
+    if version.trim_start_matches('v') != expected {
+        bail!("it reports version {version}");
+    }
+    Ok(())
+}
 
-    let remote_version = tag_name.trim_start_matches('v');
+fn binary_version(exe: &Path) -> Result<String> {
+    let output = Command::new(exe)
+        .arg("--version")
+        .output()
+        .wrap_err_with(|| format!("failed to run {}", exe.display()))?;
+    if !output.status.success() {
+        bail!("`{} --version` exited with {}", exe.display(), output.status);
+    }
 
-    debug!("current version: {VERSION}, remote version: {remote_version}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.split_whitespace().nth(1) {
+        Some(version) if stdout.starts_with("foundryup ") => Ok(version.to_string()),
+        _ => bail!("unexpected `--version` output: {}", stdout.trim()),
+    }
+}
+
+/// Returns the digest the attestation of a foundryup release records for the given binary.
+///
+/// The attestation must come from a build of the foundryup repository and be served by GitHub.
+/// Only its in-toto statement is checked, the Sigstore signature and certificate chain are not
+/// verified, so this guards against corrupted downloads rather than a compromised GitHub.
+async fn fetch_expected_hash(
+    downloader: &Downloader,
+    attestation_url: &str,
+    binary_name: &str,
+) -> Result<String> {
+    say!("downloading attestation...");
+    let Some(attestation_link) =
+        install::fetch_attestation_link(downloader, attestation_url).await?
+    else {
+        bail!("no attestation found for this release");
+    };
+    let bundle = downloader.download_to_string(&format!("{attestation_link}/download")).await?;
+    let statement = install::parse_attestation_statement(&bundle)?;
 
+    check_statement(&statement, binary_name)
+}
+
+/// Checks an attestation statement was built from the foundryup repository, and returns the
+/// digest of the given binary.
+fn check_statement(statement: &Value, binary_name: &str) -> Result<String> {
+    let repository =
+        statement["predicate"]["buildDefinition"]["externalParameters"]["workflow"]["repository"]
+            .as_str()
+            .unwrap_or_default();
+    let expected_repository = format!("https://github.com/{FOUNDRYUP_REPO}");
+    if repository != expected_repository {
+        bail!("attestation was built from '{repository}' instead of {expected_repository}");
+    }
+
+    let digests = install::statement_digests(statement);
+    let asset_name = binary_name.trim_end_matches(".exe");
+    match digests.get(binary_name).or_else(|| digests.get(asset_name)) {
+        Some(digest) => Ok(digest.clone()),
+        None => bail!("attestation has no digest for {binary_name}"),
+    }
+}
+
+/// Returns the newer version of foundryup available on the channel, if any.
+///
+/// The result is cached and the releases are fetched again only once `interval` has passed. Nothing
+/// is reported when the version is pinned through `FOUNDRYUP_VERSION`.
+pub(crate) async fn check_for_update(
+    config: &Config,
+    args: &SelfUpdateArgs,
+    interval: Duration,
+) -> Result<Option<String>> {
+    if args.to.is_some() {
+        return Ok(None);
+    }
+
+    let cache_dir = config.cache_dir(FOUNDRYUP_REPO);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let channel = args.channel.as_str();
+    if let Some(check) = UpdateCheck::read(&cache_dir)
+        && is_fresh(&check, channel, interval, now)
+    {
+        debug!("using the update check from {}", check.checked_at);
+        return newer_than_installed(check.latest.and_then(|v| Version::parse(&v).ok()));
+    }
+
+    let latest = latest_version(args.channel).await?;
+    let check = UpdateCheck {
+        checked_at: now,
+        channel: channel.to_string(),
+        latest: latest.as_ref().map(Version::to_string),
+    };
+    if let Err(e) = check.write(&cache_dir) {
+        debug!("failed to cache the update check: {e}");
+    }
+    newer_than_installed(latest)
+}
+
+/// Returns whether a cached check of the channel is recent enough to be reused.
+fn is_fresh(check: &UpdateCheck, channel: &str, interval: Duration, now: u64) -> bool {
+    check.channel == channel && now.saturating_sub(check.checked_at) < interval.as_secs()
+}
+
+/// Returns the latest version if it is newer than the installed one.
+fn newer_than_installed(latest: Option<Version>) -> Result<Option<String>> {
     let current = Version::parse(VERSION).wrap_err("failed to parse current version")?;
-    let remote = match Version::parse(remote_version) {
-        Ok(v) => v,
-        Err(e) => {
-            debug!("failed to parse remote version '{remote_version}': {e}");
-            return Ok(None);
+    let Some(remote) = latest else { return Ok(None) };
+
+    debug!("current version: {VERSION}, remote version: {remote}");
+
+    if remote > current { Ok(Some(remote.to_string())) } else { Ok(None) }
+}
+
+/// Returns the latest version released on the channel.
+async fn latest_version(channel: UpdateChannel) -> Result<Option<Version>> {
+    let downloader = Downloader::new()?;
+
+    let tags = match channel {
+        UpdateChannel::Stable => {
+            let releases_url =
+                format!("https://api.github.com/repos/{FOUNDRYUP_REPO}/releases/latest");
+            debug!("fetching latest release from {releases_url}");
+
+            let response = downloader
+                .download_to_string(&releases_url)
+                .await
+                .wrap_err("failed to fetch release information")?;
+            let json: serde_json::Value =
+                serde_json::from_str(&response).wrap_err("failed to parse release JSON")?;
+            let tag_name = json["tag_name"]
+                .as_str()
+                .ok_or_else(|| eyre::eyre!("missing tag_name in release response"))?;
+            vec![tag_name.to_string()]
+        }
+        UpdateChannel::Prerelease => {
+            debug!("fetching releases of {FOUNDRYUP_REPO}");
+            releases::fetch_releases(&downloader, FOUNDRYUP_REPO, |_| true)
+                .await?
+                .into_iter()
+                .map(|release| release.tag)
+                .collect()
         }
     };
 
-    if remote > current { Ok(Some(remote_version.to_string())) } else { Ok(None) }
+    let latest = newest_version(&tags);
+    if latest.is_none() {
+        debug!("no release version found in {tags:?}");
+    }
+    Ok(latest)
+}
+
+/// Returns the highest semver version among the release tags.
+fn newest_version(tags: &[String]) -> Option<Version> {
+    tags.iter()
+        .filter_map(|tag| match Version::parse(tag.trim_start_matches('v')) {
+            Ok(version) => Some(version),
+            Err(e) => {
+                debug!("failed to parse release version '{tag}': {e}");
+                None
+            }
+        })
+        .max()
+}
+
+/// Checks that the version of foundryup was released, so that downgrades fail early.
+async fn check_released(version: &str) -> Result<()> {
+    let downloader = Downloader::new()?;
+    let release_url =
+        format!("https://api.github.com/repos/{FOUNDRYUP_REPO}/releases/tags/v{version}");
+    downloader
+        .download_to_string(&release_url)
+        .await
+        .wrap_err_with(|| format!("foundryup v{version} was not found"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn statement(repository: &str) -> Value {
+        json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "subject": [{ "name": "foundryup_linux_amd64", "digest": { "sha256": "abc123" } }],
+            "predicateType": "https://slsa.dev/provenance/v1",
+            "predicate": {
+                "buildDefinition": {
+                    "externalParameters": { "workflow": { "repository": repository } }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn checks_attestation_statement() {
+        let repository = format!("https://github.com/{FOUNDRYUP_REPO}");
+        let attested = statement(&repository);
+        assert_eq!(check_statement(&attested, "foundryup_linux_amd64").unwrap(), "abc123");
+        assert!(check_statement(&attested, "foundryup_darwin_arm64").is_err());
+
+        let forked = statement("https://github.com/attacker/foundryup");
+        assert!(check_statement(&forked, "foundryup_linux_amd64").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn smoke_tests_the_reported_version() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("foundryup");
+        fs::write(&exe, "#!/bin/sh\necho 'foundryup 0.0.5 (abc1234 2026-10-01)'\n").unwrap();
+        fs::set_permissions(&exe, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(smoke_test(&exe, "0.0.5").is_ok());
+        assert!(smoke_test(&exe, "0.0.6").is_err());
+        assert!(smoke_test(&dir.path().join("missing"), "0.0.5").is_err());
+    }
+
+    #[test]
+    fn picks_newest_version() {
+        let tags = ["v0.0.3", "v0.1.0-rc.1", "nightly", "v0.0.10"].map(String::from);
+        assert_eq!(newest_version(&tags), Some(Version::parse("0.1.0-rc.1").unwrap()));
+        assert_eq!(newest_version(&tags[..1]), Some(Version::new(0, 0, 3)));
+        assert_eq!(newest_version(&["nightly".to_string()]), None);
+    }
+
+    #[test]
+    fn reuses_recent_update_checks() {
+        let check = UpdateCheck {
+            checked_at: 1_000,
+            channel: "stable".into(),
+            latest: Some("1.0.0".into()),
+        };
+        let day = Duration::from_secs(86_400);
+        assert!(is_fresh(&check, "stable", day, 1_000 + 3_600));
+        assert!(!is_fresh(&check, "stable", day, 1_000 + 86_400));
+        assert!(!is_fresh(&check, "prerelease", day, 1_000));
+        assert!(!is_fresh(&check, "stable", Duration::ZERO, 1_000));
+    }
 }
@@ -0,0 +1,171 @@
+use crate::config::Config;
+use eyre::{Result, WrapErr};
+use fs_err as fs;
+use serde_json::{Value, json};
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The installed version whose binaries are currently linked into the bin directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ActiveVersion {
+    pub repo: String,
+    pub version: String,
+}
+
+impl ActiveVersion {
+    /// Returns the active version, as recorded on activation.
+    ///
+    /// Falls back to resolving the bin directory symlinks for installations activated before the
+    /// active version was recorded.
+    pub(crate) fn read(config: &Config) -> Result<Option<Self>> {
+        let path = Self::path(config);
+        if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            let json: Value = serde_json::from_str(&content)
+                .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+            if let (Some(repo), Some(version)) = (json["repo"].as_str(), json["version"].as_str()) {
+                return Ok(Some(Self { repo: repo.to_string(), version: version.to_string() }));
+            }
+        }
+
+        Ok(Self::from_symlinks(config))
+    }
+
+    /// Records this version as the active one.
+    pub(crate) fn write(&self, config: &Config) -> Result<()> {
+        let json = json!({ "repo": self.repo, "version": self.version });
+        fs::write(Self::path(config), serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+
+    fn from_symlinks(config: &Config) -> Option<Self> {
+        config.network.bins.iter().find_map(|bin| {
+            let target = std::fs::read_link(config.bin_path(bin)).ok()?;
+            let version_dir = target.parent()?.strip_prefix(&config.versions_dir).ok()?;
+            let version = version_dir.file_name()?.to_str()?.to_string();
+            let repo = version_dir.parent()?.to_str()?.replace('\\', "/");
+            Some(Self { repo, version })
+        })
+    }
+
+    fn path(config: &Config) -> PathBuf {
+        config.foundry_dir.join("active.json")
+    }
+}
+
+/// Maximum number of activations kept in the history.
+const MAX_HISTORY: usize = 100;
+
+/// An entry of the activation history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Activation {
+    /// Unix timestamp of the activation, in seconds.
+    pub timestamp: u64,
+    pub repo: String,
+    pub version: String,
+    /// How the version was activated: `release`, `source` or `use`.
+    pub source: String,
+}
+
+impl Activation {
+    /// Reads the activation history, oldest first.
+    pub(crate) fn history(config: &Config) -> Result<Vec<Self>> {
+        let path = Self::path(config);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let json: Value = serde_json::from_str(&content)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+
+        let history = json
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                Some(Self {
+                    timestamp: entry["timestamp"].as_u64()?,
+                    repo: entry["repo"].as_str()?.to_string(),
+                    version: entry["version"].as_str()?.to_string(),
+                    source: entry["source"].as_str()?.to_string(),
+                })
+            })
+            .collect();
+        Ok(history)
+    }
+
+    /// Appends an activation of the given version to the history.
+    pub(crate) fn record(config: &Config, repo: &str, version: &str, source: &str) -> Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+
+        let mut history = Self::history(config)?;
+        history.push(Self {
+            timestamp,
+            repo: repo.to_string(),
+            version: version.to_string(),
+            source: source.to_string(),
+        });
+        let excess = history.len().saturating_sub(MAX_HISTORY);
+
+        let json: Vec<Value> = history[excess..]
+            .iter()
+            .map(|entry| {
+                json!({
+                    "timestamp": entry.timestamp,
+                    "repo": entry.repo,
+                    "version": entry.version,
+                    "source": entry.source,
+                })
+            })
+            .collect();
+        fs::write(Self::path(config), serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+
+    fn path(config: &Config) -> PathBuf {
+        config.foundry_dir.join("history.json")
+    }
+}
+
+/// The components selected for each repository, persisted across installs.
+pub(crate) struct Components;
+
+impl Components {
+    /// Returns the components selected for the network's repository, if a selection was made.
+    pub(crate) fn read(config: &Config) -> Result<Option<Vec<String>>> {
+        let path = Self::path(config);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let json: Value = serde_json::from_str(&content)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+        let components = json[config.network.repo]
+            .as_array()
+            .map(|bins| bins.iter().filter_map(|bin| bin.as_str().map(str::to_string)).collect());
+        Ok(components)
+    }
+
+    /// Persists the selected components of the network's repository.
+    pub(crate) fn write(config: &Config, components: &[&str]) -> Result<()> {
+        let path = Self::path(config);
+        let mut json = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .filter(Value::is_object)
+            .unwrap_or_else(|| json!({}));
+        json[config.network.repo] = json!(components);
+
+        fs::create_dir_all(&config.foundry_dir)?;
+        fs::write(path, serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+
+    fn path(config: &Config) -> PathBuf {
+        config.foundry_dir.join("components.json")
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    config::{Config, FOUNDRYUP_REPO, NetworkConfig},
+    confirm,
+    lock::FoundryLock,
+    path, say, self_update,
+};
+use eyre::{Result, WrapErr, bail};
+use fs_err as fs;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// State files written by foundryup into the foundry directory.
+const STATE_FILES: &[&str] = &["active.json", "history.json", "components.json"];
+
+/// Removes everything foundryup installed, its PATH modifications and finally its own binary.
+///
+/// Data written by the Foundry binaries themselves (e.g. keystores, `cache/rpc`, `foundry.toml`)
+/// is left in place.
+pub(crate) async fn run(config: &Config, yes: bool, lock_timeout: Duration) -> Result<()> {
+    let lock = FoundryLock::acquire(config, lock_timeout).await?;
+
+    let items = managed_items(config)?;
+    say!("this will uninstall foundryup and remove:");
+    for (path, size) in &items {
+        say!("  {} ({})", path.display(), format_size(*size));
+    }
+    say!("  the PATH setup added to your shell profiles");
+    if let Ok(exe) = std::env::current_exe() {
+        say!("  {}", exe.display());
+    }
+
+    if !yes && !confirm("continue?")? {
+        bail!("aborted, nothing was removed");
+    }
+
+    path::undo(config)?;
+    for (path, _) in &items {
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+        if let Some(parent) = path.parent() {
+            remove_empty_dirs(parent, &config.foundry_dir);
+        }
+    }
+
+    self_replace::self_delete().wrap_err("failed to remove the foundryup binary")?;
+    let _ = std::fs::remove_dir(&config.bin_dir);
+
+    drop(lock);
+    let _ = std::fs::remove_file(config.foundry_dir.join(".lock"));
+    let _ = std::fs::remove_dir(&config.foundry_dir);
+
+    say!("foundryup was uninstalled, thanks for using Foundry!");
+    Ok(())
+}
+
+/// Returns the files and directories managed by foundryup, with their sizes.
+fn managed_items(config: &Config) -> Result<Vec<(PathBuf, u64)>> {
+    let mut paths = vec![
+        config.versions_dir.clone(),
+        config.man_dir.clone(),
+        config.foundry_dir.join("completions"),
+        config.worktrees_dir(),
+        config.foundry_dir.join("logs"),
+    ];
+
+    for network in NetworkConfig::ALL {
+        paths.extend(network.bins.iter().map(|bin| config.bin_path(bin)));
+        paths.push(config.cache_dir(network.repo));
+    }
+    paths.push(config.cache_dir(FOUNDRYUP_REPO));
+    paths.extend(STATE_FILES.iter().map(|file| config.foundry_dir.join(file)));
+    for repo in cloned_repos(config)? {
+        // The cache of a source-built fork, e.g. its shared cargo target directory.
+        if let Ok(name) = repo.strip_prefix(&config.foundry_dir) {
+            paths.push(config.cache_dir(&name.to_string_lossy()));
+        }
+        paths.push(repo);
+    }
+    paths.extend(build_caches(config));
+    if let Ok(exe) = std::env::current_exe().and_then(fs::canonicalize) {
+        paths.push(self_update::backup_path(&exe));
+    }
+
+    let mut items = Vec::new();
+    for path in paths {
+        if path.symlink_metadata().is_err() || items.iter().any(|(item, _)| item == &path) {
+            continue;
+        }
+        let size = dir_size(&path);
+        items.push((path, size));
+    }
+    Ok(items)
+}
+
+/// Returns the repositories cloned for source builds, `<foundry_dir>/<owner>/<repo>`.
+pub(crate) fn cloned_repos(config: &Config) -> Result<Vec<PathBuf>> {
+    let mut repos = Vec::new();
+    for owner in fs::read_dir(&config.foundry_dir).into_iter().flatten() {
+        let owner = owner?.path();
+        if !owner.is_dir() || owner == config.versions_dir {
+            continue;
+        }
+        for repo in fs::read_dir(&owner)? {
+            let repo = repo?.path();
+            if repo.join(".git").exists() {
+                repos.push(repo);
+            }
+        }
+    }
+    Ok(repos)
+}
+
+/// Returns the caches of repositories with a shared cargo target directory, including those whose
+/// clone was removed.
+fn build_caches(config: &Config) -> Vec<PathBuf> {
+    let cache = config.foundry_dir.join("cache");
+    let mut caches = Vec::new();
+    for owner in std::fs::read_dir(cache).into_iter().flatten().flatten() {
+        for repo in std::fs::read_dir(owner.path()).into_iter().flatten().flatten() {
+            if repo.path().join("target").is_dir() {
+                caches.push(repo.path());
+            }
+        }
+    }
+    caches
+}
+
+/// Removes a directory and its parents below `root`, as long as they are empty.
+fn remove_empty_dirs(dir: &Path, root: &Path) {
+    let mut dir = Some(dir);
+    while let Some(current) = dir.filter(|dir| dir.starts_with(root) && *dir != root) {
+        if std::fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = path.symlink_metadata() else { return 0 };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    std::fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| dir_size(&entry.path()))
+        .sum()
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{bytes} B") } else { format!("{size:.1} {}", UNITS[unit]) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(300 * 1024 * 1024), "300.0 MiB");
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    cli::VerifyArgs,
+    config::Config,
+    download::Downloader,
+    install::{self, BinaryStatus},
+    metadata::VersionMetadata,
+    say,
+    state::ActiveVersion,
+};
+use eyre::{Result, bail};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+pub(crate) async fn run(config: &Config, args: &VerifyArgs) -> Result<()> {
+    let versions = if args.all {
+        install::installed_versions(config)?
+    } else {
+        match ActiveVersion::read(config)? {
+            Some(active) => vec![(active.repo, active.version)],
+            None => bail!("no active version found, use --all to verify every installed version"),
+        }
+    };
+
+    let downloader = if args.refetch { Some(Downloader::new()?) } else { None };
+
+    let mut reports = Vec::with_capacity(versions.len());
+    let mut failures = 0;
+
+    for (repo, version) in versions {
+        let version_dir = config.version_dir(&repo, &version);
+        if !version_dir.exists() {
+            bail!("version {version} not installed for {repo}");
+        }
+
+        let metadata = VersionMetadata::read(&version_dir)?.unwrap_or_default();
+        let (digests, source) = match downloader {
+            Some(ref downloader)
+                if repo == config.network.repo && config.network.has_attestation =>
+            {
+                match refetch_attestation(config, downloader, &version, &metadata).await? {
+                    Some(digests) => (digests, Some("attestation".to_string())),
+                    None => (HashMap::new(), None),
+                }
+            }
+            _ => (metadata.digests, metadata.digest_source),
+        };
+
+        let mut binaries = Vec::new();
+        for check in install::check_binaries(config, &version_dir, &digests)? {
+            if !check.path.exists()
+                && (check.status == BinaryStatus::Unverified || !config.is_selected(check.bin))
+            {
+                continue;
+            }
+
+            let (status, actual) = match check.status {
+                BinaryStatus::Verified => ("ok", check.expected.clone()),
+                BinaryStatus::Modified { actual } => ("modified", Some(actual)),
+                BinaryStatus::Missing => ("missing", None),
+                BinaryStatus::Unverified => ("unverified", None),
+            };
+            if status != "ok" {
+                failures += 1;
+            }
+
+            binaries.push(json!({
+                "name": check.bin,
+                "status": status,
+                "expected": check.expected,
+                "actual": actual,
+            }));
+        }
+
+        reports.push(json!({
+            "repo": repo,
+            "version": version,
+            "source": source,
+            "binaries": binaries,
+        }));
+    }
+
+    if args.json {
+        let report = json!({ "ok": failures == 0, "versions": reports });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&reports);
+    }
+
+    if failures > 0 {
+        bail!("{failures} binaries failed verification");
+    }
+    if !args.json {
+        say!("all binaries verified ✓");
+    }
+
+    Ok(())
+}
+
+/// Downloads the attestation of a prebuilt version again, for the target it was installed for.
+async fn refetch_attestation(
+    config: &Config,
+    downloader: &Downloader,
+    version: &str,
+    metadata: &VersionMetadata,
+) -> Result<Option<HashMap<String, String>>> {
+    let (archive_version, tag) = install::normalize_version(version);
+    let release_url =
+        format!("https://github.com/{}/releases/download/{tag}/", config.network.repo);
+
+    let target = install::installed_target(metadata)?;
+    install::fetch_attestation(downloader, &release_url, &archive_version, &target).await
+}
+
+fn print_report(reports: &[Value]) {
+    for report in reports {
+        let source = report["source"].as_str().unwrap_or("no recorded digests");
+        let repo = report["repo"].as_str().unwrap_or_default();
+        let version = report["version"].as_str().unwrap_or_default();
+        say!("{repo} {version} ({source})");
+
+        for binary in report["binaries"].as_array().into_iter().flatten() {
+            let name = binary["name"].as_str().unwrap_or_default();
+            match binary["status"].as_str().unwrap_or_default() {
+                "ok" => say!("- {name} ok"),
+                "modified" => {
+                    say!("- {name} MODIFIED");
+                    say!("    expected: {}", binary["expected"].as_str().unwrap_or_default());
+                    say!("    actual:   {}", binary["actual"].as_str().unwrap_or_default());
+                }
+                "missing" => say!("- {name} MISSING"),
+                _ => say!("- {name} UNVERIFIED (no recorded digest)"),
+            }
+        }
+        eprintln!();
+    }
+}
@@ -8,7 +8,10 @@ mod installer_script;
 mod self_update;
 
 fn foundryup() -> Command {
-    Command::new(snapbox::cmd::cargo_bin!("foundryup")).env("NO_COLOR", "1")
+    // Never modify the shell profiles of the machine running the tests.
+    Command::new(snapbox::cmd::cargo_bin!("foundryup"))
+        .env("NO_COLOR", "1")
+        .env("FOUNDRYUP_NO_MODIFY_PATH", "1")
 }
 
 fn run_forge_test(foundry_dir: &Path, temp_dir: &Path) {
@@ -27,14 +30,26 @@ forge [..]
 
 #[test]
 fn help() {
-    foundryup().arg("--help").assert().success().stdout_eq(str![[r#"
+    let cmd = foundryup().env_remove("FOUNDRYUP_NO_MODIFY_PATH");
+    cmd.arg("--help").assert().success().stdout_eq(str![[r#"
 The installer for Foundry.
 
 Update or revert to a specific Foundry version with ease.
 
 By default, the latest stable version is installed from built binaries.
 
-Usage: foundryup[EXE] [OPTIONS]
+Usage: foundryup[EXE] [OPTIONS] [COMMAND]
+
+Commands:
+  verify      Verify installed binaries against their attested or recorded digests
+  nightlies   List recent nightly releases with their publication dates
+  bisect      Find the first bad nightly between a good and a bad version
+  rollback    Reactivate the previously active version
+  gc          Remove the git worktrees and failed build logs of source builds
+  component   Add or remove binaries of the active version
+  setup-path  Add the bin directory to PATH in the shell profiles
+  self        Manage the foundryup installation itself
+  help        Print this message or the help of the given subcommand(s)
 
 Options:
   -U, --update
@@ -47,7 +62,9 @@ Options:
           Build and install a specific branch
 
   -i, --install <VERSION>
-          Install a specific version from built binaries (e.g., stable, nightly, 0.3.0)
+          Install a specific version from built binaries (e.g., stable, nightly, 1.5, latest)
+          
+          Nightlies can also be selected by date (nightly-2026-10-01) or commit (nightly@1c57854).
 
   -l, --list
           List installed versions
@@ -61,9 +78,50 @@ Options:
   -P, --pr <PR>
           Build and install a specific Pull Request
 
+      --tag <TAG>
+          Build and install a specific tag (e.g., v1.6.0-rc1)
+
+      --ref <REF>
+          Build and install an arbitrary git ref (e.g., refs/pull/123/merge)
+
   -C, --commit <COMMIT>
           Build and install a specific commit
 
+      --components <BINS>
+          Only install and link these binaries (e.g., forge,cast)
+          
+          The selection is remembered for later installs.
+          
+          [env: FOUNDRYUP_COMPONENTS=]
+
+      --clone <MODE>
+          How much history to clone for source builds
+          
+          An existing clone keeps its history, except that a shallow clone is completed with `full`.
+
+          Possible values:
+          - full:    The complete history
+          - partial: The complete history, downloading file contents only when checked out
+          - shallow: Only the built commit
+          
+          [env: FOUNDRYUP_CLONE=]
+          [default: partial]
+
+      --git-url <URL>
+          URL to clone source builds from, `{repo}` is replaced with the repository
+          
+          E.g. `git@github.com:{repo}.git` to clone over SSH, or the URL of a mirror.
+          
+          [env: FOUNDRYUP_GIT_URL=]
+          [default: https://github.com/{repo}]
+
+      --git-reference <DIR>
+          Repository to borrow git objects from when cloning for source builds
+          
+          Defaults to the clone of the upstream repository when building a fork.
+          
+          [env: FOUNDRYUP_GIT_REFERENCE=]
+
   -j, --jobs <JOBS>
           Number of CPUs to use for building (default: all)
 
@@ -75,6 +133,13 @@ Options:
       --cargo-features <CARGO_FEATURES>
           Cargo features to enable for building
 
+      --portable
+          Build without `-C target-cpu=native`, so the binaries also run on other CPUs
+          
+          Has no effect when RUSTFLAGS is set.
+          
+          [env: FOUNDRYUP_PORTABLE=]
+
   -n, --network <NETWORK>
           Install binaries for a specific network (e.g., tempo)
           
@@ -83,15 +148,51 @@ Options:
   -f, --force
           Skip SHA verification (INSECURE)
 
+      --wait
+          Wait for running Foundry binaries to exit before installing
+
+      --kill
+          Terminate running Foundry binaries before installing
+
+  -y, --yes
+          Skip confirmation prompts
+
+      --no-modify-path
+          Do not add the bin directory to PATH in the shell profiles
+          
+          [env: FOUNDRYUP_NO_MODIFY_PATH=]
+
       --arch <ARCH>
           Install a specific architecture (amd64, arm64)
 
       --platform <PLATFORM>
           Install a specific platform (win32, linux, darwin, alpine)
 
+      --no-update-check
+          Do not check for foundryup updates in the background
+          
+          The check is also skipped when stdout is not a terminal or `CI` is set.
+          
+          [env: FOUNDRYUP_NO_UPDATE_CHECK=]
+
+      --update-check-interval <SECONDS>
+          Seconds between background checks for foundryup updates
+          
+          [env: FOUNDRYUP_UPDATE_CHECK_INTERVAL=]
+          [default: 86400]
+
+      --lock-timeout <SECONDS>
+          Seconds to wait for another foundryup process to finish installing or activating, or for
+          running Foundry binaries to exit with --wait
+          
+          [env: FOUNDRYUP_LOCK_TIMEOUT=]
+          [default: 300]
+
       --completions <SHELL>
           Generate shell completions
           
+          To also complete installed and released versions, source `COMPLETE=<SHELL> foundryup`.
+          
           [possible values: bash, elvish, fish, powershell, zsh]
 
   -h, --help
@@ -123,7 +224,8 @@ fn completions_bash() {
 
 #[test]
 fn conflicting_args() {
-    foundryup().args(["--pr", "123", "--branch", "main"]).assert().failure().stderr_eq(str![[r#"
+    let cmd = foundryup().env_remove("FOUNDRYUP_NO_MODIFY_PATH");
+    cmd.args(["--pr", "123", "--branch", "main"]).assert().failure().stderr_eq(str![[r#"
 error: the argument '--pr <PR>' cannot be used with '--branch <BRANCH>'
 
 Usage: foundryup[EXE] --pr <PR>
@@ -195,6 +297,756 @@ foundryup: migrating legacy version [..]
     assert!(versions_dir.join("foundry-rs/foundry/stable").exists());
 }
 
+/// Creates a fake installed version with the given binary contents and recorded digests.
+fn fake_version(foundry_dir: &Path, version: &str, contents: &str, digest: &str) {
+    let version_dir = foundry_dir.join("versions/foundry-rs/foundry").join(version);
+    std::fs::create_dir_all(&version_dir).unwrap();
+
+    let mut digests = serde_json::Map::new();
+    for bin in BINS {
+        std::fs::write(version_dir.join(format!("{bin}{EXE_SUFFIX}")), contents).unwrap();
+        digests.insert(bin.to_string(), digest.into());
+    }
+
+    let metadata = serde_json::json!({ "digests": digests, "digest_source": "local" });
+    std::fs::write(version_dir.join(".foundryup.json"), metadata.to_string()).unwrap();
+}
+
+fn sha256_hex(contents: &str) -> String {
+    use sha2::Digest;
+    hex::encode(sha2::Sha256::digest(contents))
+}
+
+#[test]
+fn use_verified_version() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+
+    for &bin in BINS {
+        let name = format!("{bin}{EXE_SUFFIX}");
+        assert!(foundry_dir.join("bin").join(&name).exists(), "{name} does not exist");
+    }
+}
+
+#[test]
+fn use_tampered_version() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "tampered binary", &sha256_hex("fake binary"));
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--use", "v1.0.0"])
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+foundryup: forge was modified since installation:
+...
+[..]binaries of version v1.0.0 do not match the digests recorded at install time[..]
+...
+"#]]);
+    assert!(!foundry_dir.join(format!("bin/forge{EXE_SUFFIX}")).exists());
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--use", "v1.0.0", "--force"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+...
+foundryup: warning: activating modified binaries of version v1.0.0 due to --force flag
+...
+"#]]);
+}
+
+#[test]
+fn use_channel_alias() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    std::fs::write(
+        foundry_dir.join("versions/foundry-rs/foundry/.channels.json"),
+        r#"{ "stable": "v1.0.0" }"#,
+    )
+    .unwrap();
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--use", "stable"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+foundryup: stable → v1.0.0
+...
+"#]]);
+
+    // Channels are listed as recorded, without resolving them again.
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("--list").assert().success().stderr_eq(str![
+        [r#"
+foundryup: foundry-rs/foundry v1.0.0
+foundryup: - forge (unknown version)
+foundryup: - cast (unknown version)
+foundryup: - anvil (unknown version)
+foundryup: - chisel (unknown version)
+foundryup: - manpages (not installed)
+
+foundryup: foundry-rs/foundry stable → v1.0.0
+
+"#]
+    ]);
+
+    // Newer releases are reported from the tags fetched by earlier commands.
+    let cache_dir = foundry_dir.join("cache/foundry-rs/foundry");
+    std::fs::create_dir_all(&cache_dir).unwrap();
+    std::fs::write(cache_dir.join("tags.json"), r#"["v1.1.0", "v1.0.0"]"#).unwrap();
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("--list").assert().success().stderr_eq(str![
+        [r#"
+...
+foundryup: foundry-rs/foundry stable → v1.0.0 (newer v1.1.0 available)
+
+"#]
+    ]);
+}
+
+#[cfg(unix)]
+#[test]
+fn migrate_channel_dirs() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    // A version installed as `stable` before channels were resolved to release tags.
+    let legacy_dir = foundry_dir.join("versions/foundry-rs/foundry/stable");
+    std::fs::create_dir_all(&legacy_dir).unwrap();
+    std::fs::create_dir_all(foundry_dir.join("bin")).unwrap();
+    for bin in BINS {
+        let path = legacy_dir.join(bin);
+        std::fs::write(&path, format!("#!/bin/sh\necho '{bin} Version: 1.0.0-stable'\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink(&path, foundry_dir.join("bin").join(bin)).unwrap();
+    }
+
+    // Read-only commands leave the directory alone.
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("--list").assert().success();
+    assert!(legacy_dir.exists());
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("gc").assert().success().stderr_eq(str![[
+        r#"
+foundryup: migrating legacy version 'stable' of foundry-rs/foundry to v1.0.0
+foundryup: nothing to clean up
+
+"#
+    ]]);
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("--list").assert().success().stderr_eq(str![
+        [r#"
+foundryup: foundry-rs/foundry v1.0.0
+foundryup: - forge Version: 1.0.0-stable
+foundryup: - cast Version: 1.0.0-stable
+foundryup: - anvil Version: 1.0.0-stable
+foundryup: - chisel Version: 1.0.0-stable
+foundryup: - manpages (not installed)
+
+foundryup: foundry-rs/foundry stable → v1.0.0
+
+"#]
+    ]);
+
+    assert!(!legacy_dir.exists());
+    let version_dir = foundry_dir.join("versions/foundry-rs/foundry/v1.0.0");
+    assert_eq!(
+        std::fs::read_link(foundry_dir.join("bin/forge")).unwrap(),
+        version_dir.join("forge")
+    );
+}
+
+#[test]
+fn use_version_requirement() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    for version in ["v1.0.0", "v1.1.0", "v2.0.0"] {
+        fake_version(&foundry_dir, version, "fake binary", &sha256_hex("fake binary"));
+    }
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "1"]).assert().success().stderr_eq(
+        str![[r#"
+foundryup: 1 → v1.1.0
+...
+"#]],
+    );
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--use", ">=3"])
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+...
+[..]no installed version of foundry-rs/foundry matches >=3[..]
+...
+"#]]);
+}
+
+#[test]
+fn use_nightly_by_commit() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    let nightly = "nightly-1c57854462289b2e71ee7654cd6666217ed86ffd";
+    fake_version(&foundry_dir, nightly, "fake binary", &sha256_hex("fake binary"));
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--use", "nightly@1c57854"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+foundryup: nightly@1c57854 → nightly-1c57854462289b2e71ee7654cd6666217ed86ffd
+...
+"#]]);
+}
+
+#[test]
+fn use_waits_for_lock() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+
+    let lock_path = foundry_dir.join(".lock");
+    std::fs::write(&lock_path, "12345").unwrap();
+    let lock = fs_err::File::open(&lock_path).unwrap();
+    fs4::fs_err3::FileExt::lock(&lock).unwrap();
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--use", "v1.0.0", "--lock-timeout", "1"])
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+foundryup: waiting for another foundryup process (PID 12345) to finish...
+...
+[..]timed out after 1s waiting for another foundryup process (PID 12345) to release [..]
+...
+"#]]);
+
+    drop(lock);
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+}
+
+#[cfg(unix)]
+#[test]
+fn install_blocked_by_running_bins() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    let forge = foundry_dir.join("versions/foundry-rs/foundry/v1.0.0/forge");
+    std::fs::copy("/bin/sleep", &forge).unwrap();
+    let mut child = std::process::Command::new(&forge).arg("30").spawn().unwrap();
+
+    // Binaries outside of the foundryup directories are ignored, even with a similar name.
+    let forgery = temp_dir.path().join("forgery");
+    std::fs::copy("/bin/sleep", &forgery).unwrap();
+    let mut other = std::process::Command::new(&forgery).arg("30").spawn().unwrap();
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--install", "stable"])
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+...
+foundryup: warning: 'forge' is currently running (PID: [..])
+...
+[..]stop the running processes and try again, or pass --wait or --kill[..]
+...
+"#]]);
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--install", "stable", "--kill"])
+        .stdin("")
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+...
+[..]aborted, the running processes were not terminated[..]
+...
+"#]]);
+    assert!(child.try_wait().unwrap().is_none());
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--install", "stable", "--wait", "--lock-timeout", "1"])
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+...
+foundryup: waiting for the running processes to exit...
+...
+[..]timed out after 1s waiting for the running processes to exit[..]
+...
+"#]]);
+    assert!(child.try_wait().unwrap().is_none());
+
+    // The processes are asked to exit, then the install goes on (and fails on the platform).
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--install", "stable", "--kill", "-y", "--platform", "bogus"])
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+...
+foundryup: warning: 'forge' is currently running (PID: [..])
+...
+[..]unsupported platform: bogus[..]
+...
+"#]]);
+    use std::os::unix::process::ExitStatusExt;
+    assert_eq!(child.wait().unwrap().signal(), Some(15));
+    assert!(other.try_wait().unwrap().is_none());
+    other.kill().unwrap();
+}
+
+#[test]
+fn rollback_to_previous_version() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("rollback").assert().failure().stderr_eq(
+        str![[r#"
+...
+[..]no previously active version of foundry-rs/foundry to roll back to[..]
+...
+"#]],
+    );
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    fake_version(&foundry_dir, "v1.1.0", "fake binary", &sha256_hex("fake binary"));
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.1.0"]).assert().success();
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("rollback").assert().success().stderr_eq(
+        str![[r#"
+foundryup: rolling back to v1.0.0 (activated just now)
+...
+"#]],
+    );
+    let active = std::fs::read_to_string(foundry_dir.join("active.json")).unwrap();
+    assert!(active.contains("v1.0.0"));
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("rollback").assert().success().stderr_eq(
+        str![[r#"
+foundryup: rolling back to v1.1.0 (activated just now)
+...
+"#]],
+    );
+
+    let history = std::fs::read_to_string(foundry_dir.join("history.json")).unwrap();
+    assert_eq!(history.matches("\"source\": \"use\"").count(), 4);
+}
+
+#[test]
+fn rollback_fork_version() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    fake_version(&foundry_dir, "v1.1.0", "fake binary", &sha256_hex("fake binary"));
+    std::fs::create_dir_all(foundry_dir.join("versions/someone")).unwrap();
+    std::fs::rename(
+        foundry_dir.join("versions/foundry-rs/foundry"),
+        foundry_dir.join("versions/someone/foundry"),
+    )
+    .unwrap();
+    let history = serde_json::json!([
+        { "timestamp": 1, "repo": "someone/foundry", "version": "v1.0.0", "source": "source" },
+        { "timestamp": 2, "repo": "someone/foundry", "version": "v1.1.0", "source": "source" },
+    ]);
+    std::fs::write(foundry_dir.join("history.json"), history.to_string()).unwrap();
+    std::fs::write(
+        foundry_dir.join("active.json"),
+        r#"{ "repo": "someone/foundry", "version": "v1.1.0" }"#,
+    )
+    .unwrap();
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("rollback").assert().success().stderr_eq(
+        str![[r#"
+foundryup: rolling back to v1.0.0 (activated [..] days ago)
+...
+"#]],
+    );
+    let active = std::fs::read_to_string(foundry_dir.join("active.json")).unwrap();
+    assert!(active.contains("someone/foundry") && active.contains("v1.0.0"));
+}
+
+#[test]
+fn add_and_remove_components() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let version_dir = foundry_dir.join("versions/foundry-rs/foundry/v1.0.0");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["component", "remove", "anvil,chisel"])
+        .assert()
+        .success();
+    for (bin, kept) in [("forge", true), ("cast", true), ("anvil", false), ("chisel", false)] {
+        let bin = format!("{bin}{EXE_SUFFIX}");
+        assert_eq!(foundry_dir.join("bin").join(&bin).exists(), kept);
+        assert_eq!(version_dir.join(&bin).exists(), kept);
+    }
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["component", "list"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+foundryup: forge (installed)
+foundryup: cast (installed)
+foundryup: anvil (not selected)
+foundryup: chisel (not selected)
+...
+"#]]);
+
+    // The selection is remembered, and the version still passes its integrity check.
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+    assert!(!foundry_dir.join("bin").join(format!("anvil{EXE_SUFFIX}")).exists());
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["component", "add", "anvil"])
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+...
+[..]v1.0.0 of foundry-rs/foundry was not installed from a release, rebuild it to add components[..]
+...
+"#]]);
+
+    // A failed add does not change the selection.
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["component", "list"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+...
+foundryup: anvil (not selected)
+...
+"#]]);
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--use", "v1.0.0", "--components", "forge,anvl"])
+        .assert()
+        .failure()
+        .stderr_eq(str![[r#"
+...
+[..]unknown component 'anvl', expected one of: forge, cast, anvil, chisel[..]
+...
+"#]]);
+}
+
+#[test]
+fn use_switches_manpages() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let man_dir = foundry_dir.join("share/man/man1");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    fake_version(&foundry_dir, "v1.1.0", "fake binary", &sha256_hex("fake binary"));
+    let version_man_dir = foundry_dir.join("versions/foundry-rs/foundry/v1.0.0/share/man/man1");
+    std::fs::create_dir_all(&version_man_dir).unwrap();
+    for page in ["forge.1", "forge-build.1", "cast.1"] {
+        std::fs::write(version_man_dir.join(page), page).unwrap();
+    }
+
+    foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .env("MANPATH", temp_dir.path())
+        .args(["--use", "v1.0.0"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+...
+foundryup: hint: add [..] to MANPATH to read the manpages with `man`
+...
+"#]]);
+    assert_eq!(std::fs::read_to_string(man_dir.join("forge-build.1")).unwrap(), "forge-build.1");
+    assert_eq!(std::fs::read_dir(&man_dir).unwrap().count(), 3);
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("--list").assert().success().stderr_eq(str![
+        [r#"
+...
+foundryup: - manpages (3 pages)
+...
+foundryup: - manpages (not installed)
+...
+"#]
+    ]);
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.1.0"]).assert().success();
+    assert_eq!(std::fs::read_dir(&man_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn use_migrates_legacy_manpages() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let man_dir = foundry_dir.join("share/man/man1");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    fake_version(&foundry_dir, "v1.1.0", "fake binary", &sha256_hex("fake binary"));
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+
+    // A manpage installed into the shared directory before manpages were stored per version.
+    std::fs::write(man_dir.join("forge.1"), "forge.1").unwrap();
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.1.0"]).assert().success();
+    let version_man_dir = foundry_dir.join("versions/foundry-rs/foundry/v1.0.0/share/man/man1");
+    assert_eq!(std::fs::read_to_string(version_man_dir.join("forge.1")).unwrap(), "forge.1");
+    assert_eq!(std::fs::read_dir(&man_dir).unwrap().count(), 0);
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+    assert_eq!(std::fs::read_to_string(man_dir.join("forge.1")).unwrap(), "forge.1");
+}
+
+#[cfg(unix)]
+#[test]
+fn use_generates_completions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let home = temp_dir.path();
+    let foundry_dir = home.join(".foundry");
+
+    // `cast` hangs while generating its completions.
+    let script = "#!/bin/sh\ncase \"$0 $1\" in *cast\\ completions) sleep 30;; esac\n\
+                  echo \"complete -W build ${0##*/} # $1 $2\"\n";
+    fake_version(&foundry_dir, "v1.0.0", script, &sha256_hex(script));
+    let version_dir = foundry_dir.join("versions/foundry-rs/foundry/v1.0.0");
+    for bin in BINS {
+        let permissions = std::fs::Permissions::from_mode(0o755);
+        std::fs::set_permissions(version_dir.join(bin), permissions).unwrap();
+    }
+
+    // Only bash and zsh source the env scripts.
+    let source = format!(". \"{}\" # added by foundryup\n", foundry_dir.join("env").display());
+    std::fs::write(home.join(".bashrc"), &source).unwrap();
+    std::fs::write(home.join(".zshenv"), &source).unwrap();
+    foundryup()
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .arg("setup-path")
+        .assert()
+        .success();
+
+    foundryup()
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["--use", "v1.0.0", "--components", "forge,cast"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+...
+foundryup: generated shell completions in [..]
+...
+"#]]);
+
+    let completions_dir = foundry_dir.join("completions");
+    let bash = std::fs::read_to_string(completions_dir.join("bash/forge")).unwrap();
+    assert_eq!(bash, "complete -W build forge # completions bash\n");
+    assert!(completions_dir.join("zsh/_forge").exists());
+    assert!(!completions_dir.join("zsh/_cast").exists());
+    assert!(!completions_dir.join("fish").exists());
+    assert!(!completions_dir.join("bash/anvil").exists());
+
+    // The env script loads the completions in bash.
+    let output = std::process::Command::new("bash")
+        .args(["-c", &format!(". \"{}\"; complete -p forge", foundry_dir.join("env").display())])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "complete -W 'build' forge\n");
+}
+
+#[test]
+fn dynamic_completions() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    let cache_dir = foundry_dir.join("cache/foundry-rs/foundry");
+    std::fs::create_dir_all(&cache_dir).unwrap();
+    std::fs::write(cache_dir.join("tags.json"), r#"["v1.5.0", "nightly-abcdef"]"#).unwrap();
+
+    let complete = |args: &[&str]| {
+        foundryup().env("FOUNDRY_DIR", &foundry_dir).env("COMPLETE", "fish").args(args).assert()
+    };
+
+    // Versions of other networks are not offered.
+    let tempo_dir = foundry_dir.join("versions/tempoxyz/tempo-foundry/v0.1.0");
+    std::fs::create_dir_all(&tempo_dir).unwrap();
+
+    complete(&["--", "foundryup", "--use", ""]).success().stdout_eq(str![[r#"
+v1.0.0
+
+"#]]);
+    complete(&["--", "foundryup", "--network", "tempo", "--use", ""]).success().stdout_eq(str![[
+        r#"
+v0.1.0
+
+"#
+    ]]);
+    complete(&["--", "foundryup", "--install", ""]).success().stdout_eq(str![[r#"
+stable
+nightly
+latest
+v1.5.0
+nightly-abcdef
+
+"#]]);
+    complete(&["--", "foundryup", "--network", ""]).success().stdout_eq(str![[r#"
+tempo
+
+"#]]);
+}
+
+#[cfg(unix)]
+#[test]
+fn setup_path_in_profiles() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let home = temp_dir.path();
+    let foundry_dir = home.join(".foundry");
+    std::fs::write(home.join(".bashrc"), "alias ll='ls -l'").unwrap();
+
+    let setup_path = || {
+        foundryup()
+            .env_remove("FOUNDRYUP_NO_MODIFY_PATH")
+            .env("HOME", home)
+            .env("XDG_CONFIG_HOME", home.join(".config"))
+            .env("FOUNDRY_DIR", &foundry_dir)
+            .arg("setup-path")
+    };
+
+    setup_path().assert().success().stderr_eq(str![[r#"
+foundryup: added [..]/.foundry/bin to PATH in [..]/.profile
+foundryup: added [..]/.foundry/bin to PATH in [..]/.bashrc
+...
+"#]]);
+    setup_path().assert().success();
+
+    let line = format!(". \"{}\" # added by foundryup", foundry_dir.join("env").display());
+    let bashrc = std::fs::read_to_string(home.join(".bashrc")).unwrap();
+    assert_eq!(bashrc, format!("alias ll='ls -l'\n{line}\n"));
+    assert_eq!(std::fs::read_to_string(home.join(".profile")).unwrap(), format!("{line}\n"));
+
+    let path = format!("/usr/bin:{}", foundry_dir.join("bin").display());
+    let output = std::process::Command::new("sh")
+        .args([
+            "-c",
+            &format!(". \"{}\"; . \"{0}\"; echo $PATH", foundry_dir.join("env").display()),
+        ])
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), path);
+
+    setup_path().arg("--undo").assert().success();
+    assert_eq!(std::fs::read_to_string(home.join(".bashrc")).unwrap(), "alias ll='ls -l'\n");
+    assert!(!foundry_dir.join("env").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn setup_path_generates_completions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let home = temp_dir.path();
+    let foundry_dir = home.join(".foundry");
+    std::fs::write(home.join(".bashrc"), "").unwrap();
+
+    let script = "#!/bin/sh\necho \"complete -W build ${0##*/}\"\n";
+    fake_version(&foundry_dir, "v1.0.0", script, &sha256_hex(script));
+    let version_dir = foundry_dir.join("versions/foundry-rs/foundry/v1.0.0");
+    for bin in BINS {
+        let permissions = std::fs::Permissions::from_mode(0o755);
+        std::fs::set_permissions(version_dir.join(bin), permissions).unwrap();
+    }
+
+    // No shell loads completions when the version is activated on a first install.
+    let cmd = || {
+        foundryup()
+            .env("HOME", home)
+            .env("XDG_CONFIG_HOME", home.join(".config"))
+            .env("FOUNDRY_DIR", &foundry_dir)
+    };
+    cmd().args(["--use", "v1.0.0"]).assert().success();
+    assert!(!foundry_dir.join("completions/bash/forge").exists());
+
+    cmd().env_remove("FOUNDRYUP_NO_MODIFY_PATH").arg("setup-path").assert().success();
+    assert!(foundry_dir.join("completions/bash/forge").exists());
+}
+
+#[test]
+fn verify_installed_binaries() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    fake_version(&foundry_dir, "v1.1.0", "fake binary", &sha256_hex("fake binary"));
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("verify").assert().success().stderr_eq(str![
+        [r#"
+foundryup: foundry-rs/foundry v1.0.0 (local)
+foundryup: - forge ok
+foundryup: - cast ok
+foundryup: - anvil ok
+foundryup: - chisel ok
+
+foundryup: all binaries verified ✓
+...
+"#]
+    ]);
+
+    let cast = foundry_dir.join(format!("versions/foundry-rs/foundry/v1.1.0/cast{EXE_SUFFIX}"));
+    std::fs::write(cast, "tampered binary").unwrap();
+
+    let output = foundryup()
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["verify", "--all", "--json"])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["ok"], false);
+
+    let versions = report["versions"].as_array().unwrap();
+    assert_eq!(versions.len(), 2);
+    let tampered = versions.iter().find(|v| v["version"] == "v1.1.0").unwrap();
+    let statuses: Vec<_> =
+        tampered["binaries"].as_array().unwrap().iter().map(|b| b["status"].clone()).collect();
+    assert_eq!(statuses, ["ok", "modified", "ok", "ok"]);
+}
+
 fn test_install(version: &str) {
     let temp_dir = tempfile::Builder::new().tempdir().unwrap();
     let foundry_dir = temp_dir.path().join(".foundry");
@@ -312,3 +1164,306 @@ fn install_tempo_nightly() {
 
     run_forge_test(&foundry_dir, temp_dir.path());
 }
+
+/// Creates a git repository at `<dir>/<repo>` with a crate building a `forge` binary.
+fn fake_source_repo(dir: &Path, repo: &str) -> std::path::PathBuf {
+    let repo_dir = dir.join(repo);
+    std::fs::create_dir_all(repo_dir.join("src")).unwrap();
+    std::fs::write(
+        repo_dir.join("Cargo.toml"),
+        "[package]\nname = \"forge\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n[workspace]\n",
+    )
+    .unwrap();
+    std::fs::write(repo_dir.join("src/main.rs"), "fn main() { println!(\"forge 0.0.0\"); }\n")
+        .unwrap();
+    std::fs::write(repo_dir.join(".gitignore"), "target\n").unwrap();
+
+    Command::new("git")
+        .args(["init", "--quiet", "--initial-branch=master"])
+        .current_dir(&repo_dir)
+        .assert()
+        .success();
+    commit(&repo_dir, "initial commit");
+    repo_dir
+}
+
+/// Commits all changes of a repository created by [`fake_source_repo`].
+fn commit(repo_dir: &Path, message: &str) {
+    for args in [&["add", "--all"][..], &["commit", "--quiet", "-m", message]] {
+        Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(repo_dir)
+            .assert()
+            .success();
+    }
+}
+
+/// Returns a command building `someone/foundry` from the repositories created by
+/// [`fake_source_repo`] in `remote`.
+fn source_build(foundry_dir: &Path, remote: &Path) -> Command {
+    foundryup()
+        .env("FOUNDRY_DIR", foundry_dir)
+        .args(["--repo", "someone/foundry", "--git-url"])
+        .arg(format!("file://{}/{{repo}}", remote.display()))
+}
+
+#[cfg(unix)]
+#[test]
+fn build_from_shallow_clone() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let remote = temp_dir.path().join("remote");
+    let repo_dir = fake_source_repo(&remote, "someone/foundry");
+
+    source_build(&foundry_dir, &remote)
+        .args(["--branch", "master", "--clone", "shallow"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+...
+foundryup: cloning someone/foundry...
+...
+foundryup: installing version someone-branch-master-[..] (commit [..])
+...
+"#]]);
+
+    let clone = foundry_dir.join("someone/foundry");
+    assert!(clone.join(".git/shallow").exists());
+    Command::new(foundry_dir.join(format!("bin/forge{EXE_SUFFIX}"))).assert().success().stdout_eq(
+        str![[r#"
+forge 0.0.0
+
+"#]],
+    );
+
+    // The existing clone gets its full history when asked for.
+    std::fs::write(repo_dir.join("README.md"), "forge\n").unwrap();
+    commit(&repo_dir, "second commit");
+    source_build(&foundry_dir, &remote)
+        .args(["--branch", "master", "--clone", "full"])
+        .assert()
+        .success();
+    assert!(!clone.join(".git/shallow").exists());
+
+    // A full clone is not made shallow again.
+    std::fs::write(repo_dir.join("README.md"), "forge 0.0.0\n").unwrap();
+    commit(&repo_dir, "third commit");
+    source_build(&foundry_dir, &remote)
+        .args(["--branch", "master", "--clone", "shallow"])
+        .assert()
+        .success();
+    assert!(!clone.join(".git/shallow").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn build_from_tag() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let remote = temp_dir.path().join("remote");
+    let repo_dir = fake_source_repo(&remote, "someone/foundry");
+    Command::new("git").args(["tag", "v0.1.0-rc1"]).current_dir(&repo_dir).assert().success();
+
+    source_build(&foundry_dir, &remote)
+        .env_remove("RUSTFLAGS")
+        .args(["--tag", "v0.1.0-rc1", "--portable"])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+...
+foundryup: fetching v0.1.0-rc1...
+foundryup: installing version someone-tag-v0.1.0-rc1 (commit [..])
+...
+"#]]);
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("--list").assert().success().stderr_eq(str![
+        [r#"
+foundryup: someone/foundry someone-tag-v0.1.0-rc1
+foundryup: - commit [..]
+foundryup: - built with profile release, RUSTFLAGS "", rustc [..] on [..]
+foundryup: - forge 0.0.0
+...
+"#]
+    ]);
+}
+
+#[cfg(unix)]
+#[test]
+fn gc_removes_worktrees() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let remote = temp_dir.path().join("remote");
+    fake_source_repo(&remote, "someone/foundry");
+
+    source_build(&foundry_dir, &remote).args(["--branch", "master"]).assert().success();
+
+    let worktrees = foundry_dir.join("worktrees/someone/foundry");
+    assert_eq!(std::fs::read_dir(&worktrees).unwrap().count(), 1);
+    // The log of a successful build is not kept.
+    assert_eq!(std::fs::read_dir(foundry_dir.join("logs")).unwrap().count(), 0);
+    assert!(foundry_dir.join("cache/someone/foundry/target").exists());
+    // Left behind by builds made in the clone before worktrees were used.
+    let legacy_target = foundry_dir.join("someone/foundry/target/release");
+    std::fs::create_dir_all(&legacy_target).unwrap();
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("gc").assert().success().stderr_eq(str![[
+        r#"
+foundryup: removed the build worktrees and logs, freeing [..]
+...
+"#
+    ]]);
+    assert!(!worktrees.exists());
+    assert!(!foundry_dir.join("someone/foundry/target").exists());
+    assert!(foundry_dir.join("cache/someone/foundry/target").exists());
+    Command::new("git")
+        .args(["worktree", "list"])
+        .current_dir(foundry_dir.join("someone/foundry"))
+        .assert()
+        .success()
+        .stdout_eq(str![[r#"
+[..]/.foundry/someone/foundry [..]
+
+"#]]);
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("gc").assert().success().stderr_eq(str![[
+        r#"
+foundryup: nothing to clean up
+...
+"#
+    ]]);
+}
+
+#[cfg(unix)]
+#[test]
+fn build_reuses_built_commits() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let remote = temp_dir.path().join("remote");
+    let repo_dir = fake_source_repo(&remote, "someone/foundry");
+    let build = |args: &[&str]| source_build(&foundry_dir, &remote).args(args).assert().success();
+    let head = || {
+        let output = std::process::Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(&repo_dir)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    };
+
+    let first = head();
+    build(&["--branch", "master"]);
+    build(&["--commit", &first[..10]]).stderr_eq(str![[r#"
+...
+foundryup: someone-branch-master-[..] was already built from [..], activating it
+...
+"#]]);
+
+    // A different profile is built again.
+    build(&["--commit", &first, "--cargo-profile", "dev"]).stderr_eq(str![[r#"
+...
+foundryup: installing version someone-commit-[..] (commit [..])
+...
+"#]]);
+
+    std::fs::write(repo_dir.join("src/main.rs"), "fn main() { println!(\"forge 0.0.1\"); }\n")
+        .unwrap();
+    commit(&repo_dir, "second commit");
+    let second = head();
+    build(&["--branch", "master"]);
+
+    let versions = foundry_dir.join("versions/someone/foundry");
+    assert!(versions.join(format!("someone-branch-master-{}", &first[..7])).exists());
+    assert!(versions.join(format!("someone-branch-master-{}", &second[..7])).exists());
+
+    // Modified binaries are built again rather than reused.
+    let version_dir = versions.join(format!("someone-branch-master-{}", &second[..7]));
+    std::fs::write(version_dir.join("forge"), "tampered binary").unwrap();
+    build(&["--branch", "master"]).stderr_eq(str![[r#"
+...
+foundryup: forge was modified since installation:
+...
+foundryup: warning: not reusing someone-branch-master-[..]: binaries of version [..] do not match the digests recorded at install time; reinstall it, or pass --force to activate it anyway (INSECURE)
+...
+foundryup: installing version someone-branch-master-[..] (commit [..])
+...
+"#]]);
+    Command::new(version_dir.join("forge")).assert().success();
+}
+
+#[cfg(unix)]
+#[test]
+fn build_reuses_commits_with_pinned_toolchain() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let remote = temp_dir.path().join("remote");
+    let repo_dir = fake_source_repo(&remote, "someone/foundry");
+    std::fs::write(repo_dir.join("rust-toolchain.toml"), "[toolchain]\nchannel = \"stable\"\n")
+        .unwrap();
+    commit(&repo_dir, "pin the toolchain");
+
+    // A rustc that reports the toolchain file of the directory it runs in.
+    let output =
+        std::process::Command::new("sh").args(["-c", "command -v rustc"]).output().unwrap();
+    let rustc = String::from_utf8(output.stdout).unwrap().trim().to_string();
+    let bin_dir = temp_dir.path().join("fake-bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    std::fs::write(
+        bin_dir.join("rustc"),
+        format!(
+            "#!/bin/sh\n\"{rustc}\" \"$@\" || exit $?\n\
+             if [ \"$1\" = -vV ]; then echo \"pinned: $(cat rust-toolchain.toml 2>/dev/null)\"; fi\n"
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(bin_dir.join("rustc"), std::fs::Permissions::from_mode(0o755))
+        .unwrap();
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap());
+
+    let build = || {
+        source_build(&foundry_dir, &remote)
+            .env("PATH", &path)
+            .args(["--branch", "master"])
+            .assert()
+            .success()
+    };
+    build();
+    build().stderr_eq(str![[r#"
+...
+foundryup: someone-branch-master-[..] was already built from [..], activating it
+...
+"#]]);
+}
+
+#[cfg(unix)]
+#[test]
+fn build_failure_reports_errors() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let foundry_dir = temp_dir.path().join(".foundry");
+    let remote = temp_dir.path().join("remote");
+    let repo_dir = fake_source_repo(&remote, "someone/foundry");
+    std::fs::write(repo_dir.join("src/main.rs"), "fn main() { missing(); }\n").unwrap();
+    commit(&repo_dir, "broken commit");
+
+    source_build(&foundry_dir, &remote).args(["--branch", "master"]).assert().failure().stderr_eq(
+        str![[r#"
+...
+foundryup: installing version someone-branch-master-[..] (commit [..])
+error[E0425]: cannot find function `missing` in this scope
+...
+[..]cargo build failed, see the full log at [..]/.foundry/logs/someone-branch-master-[..].log[..]
+...
+"#]],
+    );
+
+    let logs: Vec<_> = std::fs::read_dir(foundry_dir.join("logs")).unwrap().flatten().collect();
+    assert_eq!(logs.len(), 1);
+    let log = std::fs::read_to_string(logs[0].path()).unwrap();
+    assert!(log.contains("Compiling forge v0.0.0"));
+    assert!(log.contains("cannot find function `missing`"));
+
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).arg("gc").assert().success();
+    assert!(!foundry_dir.join("logs").exists());
+}
@@ -18,3 +18,154 @@ foundryup: checking for updates...
 ...
 "#]]);
 }
+
+#[test]
+fn self_update_checks_for_updates() {
+    foundryup().args(["self", "update"]).assert().stderr_eq(str![[r#"
+...
+foundryup: checking for updates...
+...
+"#]]);
+}
+
+#[test]
+fn self_update_to_invalid_version() {
+    foundryup().args(["self", "update", "--to", "latest"]).assert().failure().stderr_eq(str![[
+        r#"
+...
+[..]invalid foundryup version 'latest', expected e.g. 0.0.3[..]
+...
+"#
+    ]]);
+}
+
+#[test]
+fn self_update_to_installed_version() {
+    foundryup()
+        .args(["self", "update", "--to", concat!("v", env!("CARGO_PKG_VERSION"))])
+        .assert()
+        .success()
+        .stderr_eq(str![[r#"
+foundryup: foundryup v[..] is already installed
+...
+"#]]);
+}
+
+#[cfg(unix)]
+#[test]
+fn self_uninstall() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let home = temp_dir.path();
+    let foundry_dir = home.join(".foundry");
+
+    fake_version(&foundry_dir, "v1.0.0", "fake binary", &sha256_hex("fake binary"));
+    foundryup().env("FOUNDRY_DIR", &foundry_dir).args(["--use", "v1.0.0"]).assert().success();
+    std::fs::create_dir_all(foundry_dir.join("keystores")).unwrap();
+    std::fs::write(foundry_dir.join("keystores/deployer"), "{}").unwrap();
+    std::fs::write(home.join(".profile"), "# added by foundryup\n").unwrap();
+
+    // Run a copy, as the binary deletes itself.
+    let exe = foundry_dir.join("bin/foundryup");
+    std::fs::copy(snapbox::cmd::cargo_bin!("foundryup"), &exe).unwrap();
+    let uninstall = || {
+        Command::new(&exe)
+            .env("NO_COLOR", "1")
+            .env("HOME", home)
+            .env("XDG_CONFIG_HOME", home.join(".config"))
+            .env("FOUNDRY_DIR", &foundry_dir)
+            .args(["self", "uninstall"])
+    };
+
+    uninstall().stdin("").assert().failure().stderr_eq(str![[r#"
+foundryup: this will uninstall foundryup and remove:
+foundryup:   [..]/.foundry/versions ([..] B)
+...
+foundryup:   [..]/.foundry/bin/forge ([..] B)
+...
+foundryup:   [..]/.foundry/active.json ([..] B)
+...
+[..]aborted, nothing was removed[..]
+...
+"#]]);
+    assert!(exe.exists());
+
+    uninstall().arg("-y").assert().success();
+    assert!(!exe.exists());
+    assert!(!foundry_dir.join("versions").exists());
+    assert!(!foundry_dir.join("bin").exists());
+    assert!(!foundry_dir.join("active.json").exists());
+    assert!(foundry_dir.join("keystores/deployer").exists());
+    assert_eq!(std::fs::read_to_string(home.join(".profile")).unwrap(), "");
+}
+
+#[cfg(unix)]
+#[test]
+fn self_uninstall_removes_build_caches() {
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let home = temp_dir.path();
+    let foundry_dir = home.join(".foundry");
+    let remote = home.join("remote");
+    fake_source_repo(&remote, "someone/foundry");
+
+    source_build(&foundry_dir, &remote).args(["--branch", "master"]).assert().success();
+    assert!(foundry_dir.join("cache/someone/foundry/target").exists());
+    // A cache whose clone was removed by hand.
+    std::fs::create_dir_all(foundry_dir.join("cache/other/foundry/target")).unwrap();
+
+    let exe = foundry_dir.join("bin/foundryup");
+    std::fs::copy(snapbox::cmd::cargo_bin!("foundryup"), &exe).unwrap();
+    Command::new(&exe)
+        .env("NO_COLOR", "1")
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("FOUNDRY_DIR", &foundry_dir)
+        .args(["self", "uninstall", "-y"])
+        .assert()
+        .success();
+    assert!(!foundry_dir.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn self_rollback() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let exe = temp_dir.path().join("foundryup");
+    std::fs::copy(snapbox::cmd::cargo_bin!("foundryup"), &exe).unwrap();
+    let rollback = || Command::new(&exe).env("NO_COLOR", "1").args(["self", "rollback"]);
+
+    rollback().assert().failure().stderr_eq(str![[r#"
+...
+[..]no previous foundryup binary found at [..]/foundryup.old[..]
+...
+"#]]);
+
+    let previous = "#!/bin/sh\necho 'foundryup 0.0.1 (previous)'\n";
+    let backup = temp_dir.path().join("foundryup.old");
+    std::fs::write(&backup, previous).unwrap();
+    std::fs::set_permissions(&backup, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    rollback().assert().success().stderr_eq(str![[r#"
+foundryup: rolled back foundryup: [..] → 0.0.1
+
+"#]]);
+    assert_eq!(std::fs::read_to_string(&exe).unwrap(), previous);
+    assert_eq!(
+        std::fs::read(&backup).unwrap(),
+        std::fs::read(snapbox::cmd::cargo_bin!("foundryup")).unwrap()
+    );
+}
+
+#[test]
+fn update_with_invalid_channel() {
+    foundryup().env("FOUNDRYUP_CHANNEL", "bogus").arg("-U").assert().failure().stderr_eq(str![[
+        r#"
+...
+[..]invalid FOUNDRYUP_CHANNEL 'bogus', expected stable or prerelease[..]
+...
+"#
+    ]]);
+
+    foundryup().env("FOUNDRYUP_CHANNEL", "bogus").arg("--list").assert().success();
+}